@@ -1,4 +1,7 @@
-use std::{array::TryFromSliceError, ops::AddAssign};
+use std::{
+    array::TryFromSliceError,
+    ops::{AddAssign, Range},
+};
 
 use bevy::{
     asset::RenderAssetUsages,
@@ -8,9 +11,11 @@ use bevy::{
 use serde::Deserialize;
 use thiserror::Error;
 
+use crate::art_atlas::{ArtAtlasSettings, PackedAtlas, pack_frames};
+
 /// Intermediate struct to decode the custom `.ART` format Arcanum uses.
-/// Since the .ART format contains multiple frames, these frames will be rendered into one image
-/// along the x-axis. Simultaneously, a TextureAtlas will be built from the meta infos
+/// Since the .ART format contains multiple frames, these frames will be packed into one atlas image.
+/// Simultaneously, a TextureAtlas will be built from the meta infos
 #[allow(dead_code)]
 #[derive(Asset, Debug, Deserialize, TypePath)]
 pub struct Art {
@@ -20,38 +25,20 @@ pub struct Art {
     frame_data: Vec<ArtFrame>,
 }
 
-pub type TigArtId = u32;
-const ART_ID_TYPE_SHIFT: u32 = 28;
-
-pub const ART_TYPE_INTERFACE: u32 = 5;
-const INTERFACE_ID_MAX_NUM: u32 = 4096;
-const INTERFACE_ID_NUM_SHIFT: u32 = 16;
-
-pub fn art_id_create(num: u32, frame: u32, a3: u8, palette: u32) -> Result<TigArtId, ()> {
-    let art_id = (ART_TYPE_INTERFACE << ART_ID_TYPE_SHIFT)
-        | ((num & (INTERFACE_ID_MAX_NUM - 1)) << INTERFACE_ID_NUM_SHIFT);
-    Ok(art_id)
-}
-
-pub fn art_type(art_id: TigArtId) -> u32 {
-    art_id >> ART_ID_TYPE_SHIFT
-}
-
-#[test]
-fn test_interface() {
-    // assert_eq!(art_id_create(327, 0, 0, 0), Ok(u32::MAX));
-    assert_eq!(
-        art_type(art_id_create(327, 0, 0, 0).unwrap()),
-        ART_TYPE_INTERFACE
-    );
-}
-
 #[derive(Debug, Error)]
 pub enum ArtError {
     #[error("Error while slicing from buffer")]
     Slice(#[from] TryFromSliceError),
     #[error("FrameData contained 0 frames")]
     EmptyFrame,
+    #[error("Frames don't fit into an atlas of {0}x{0} pixels")]
+    AtlasTooLarge(u32),
+    #[error("Art has no palette {0}")]
+    MissingPalette(usize),
+    #[error("Art has no frame {0}")]
+    MissingFrame(usize),
+    #[error("Art has no rotation {0}")]
+    MissingRotation(u32),
 }
 
 impl Art {
@@ -93,85 +80,240 @@ impl Art {
         })
     }
 
+    /// Renders all frames into one image, packed into a 2D atlas no larger than
+    /// [`crate::DEFAULT_MAX_ATLAS_SIZE`] on either side. Use [`Art::to_texture_atlas`] to get the matching layout.
     pub fn to_image(&self) -> Result<Image, ArtError> {
-        // accumulate width, so we can place all frames next to each other
-        let width = self
-            .frame_data
-            .iter()
-            .fold(0, |acc, frame| acc + frame.header.width);
-        // check for highest frame
-        let height = self
+        self.to_image_with(0, &ArtAtlasSettings::default())
+    }
+
+    /// Same as [`Art::to_image`], but expands the pixels with the given colour table instead of the first one.
+    pub fn to_image_with(
+        &self,
+        palette: usize,
+        settings: &ArtAtlasSettings,
+    ) -> Result<Image, ArtError> {
+        let atlas = self.pack(settings)?;
+        self.rgba_image(atlas.size, &self.index_data(&atlas), palette)
+    }
+
+    /// Renders a single frame into its own image.
+    pub fn frame_image(&self, frame: usize, palette: usize) -> Result<Image, ArtError> {
+        let frame = self
             .frame_data
-            .iter()
-            .map(|frame| frame.header.height)
-            .max()
-            .ok_or(ArtError::EmptyFrame)?;
-        info!("w {}, h {}", width, height);
-        let mut data = vec![0; width as usize * height as usize * 4];
-        let mut offset = 0;
-        for frame in &self.frame_data {
-            for y in (0..frame.header.height as usize).rev() {
-                for x in 0..frame.width() {
-                    let sample = frame.pixels[y][x] as usize;
-                    let target_x = x + offset;
-                    let index = (y * width as usize + target_x) * 4;
-                    match sample {
-                        0 => {
-                            data[index + 3] = 0;
-                        }
-                        _ => {
-                            let color = &self.color_table_data[0].0[sample];
-                            data[index] = color.r;
-                            data[index + 1] = color.g;
-                            data[index + 2] = color.b;
-                            data[index + 3] = color.opacity();
-                        }
-                    };
-                }
-            }
-            offset += frame.width();
+            .get(frame)
+            .ok_or(ArtError::MissingFrame(frame))?;
+        let size = UVec2::new(frame.header.width, frame.header.height);
+        self.rgba_image(size, &frame.pixels.concat(), palette)
+    }
+
+    fn rgba_image(&self, size: UVec2, indices: &[u8], palette: usize) -> Result<Image, ArtError> {
+        let colors = self.palette_data(palette)?;
+        let mut data = Vec::with_capacity(indices.len() * 4);
+        for &sample in indices {
+            let index = sample as usize * 4;
+            data.extend_from_slice(&colors[index..index + 4]);
         }
-        let image = Image::new(
+        Ok(Image::new(
             Extent3d {
-                width,
-                height,
+                width: size.x,
+                height: size.y,
                 depth_or_array_layers: 1,
             },
             TextureDimension::D2,
             data,
             TextureFormat::Rgba8UnormSrgb,
             RenderAssetUsages::all(),
-        );
-        Ok(image)
-    }
-
-    pub fn to_texture_atlas(&self) -> TextureAtlasLayout {
-        let columns = self.frame_data.len() as u32;
-        let mut textures = vec![];
-        let mut current_x = 0;
-        for frame in &self.frame_data {
-            textures.push(URect::new(
-                current_x,
-                frame.header.height,
-                current_x + frame.header.width,
-                0,
-            ));
-            current_x += frame.header.width;
+        ))
+    }
+
+    /// Renders all frames into an 8-bit image of palette indices, packed the same way as [`Art::to_image`].
+    /// Index 0 is transparent. Combine it with [`Art::to_palette_image`] to do the colour lookup on the GPU.
+    pub fn to_index_image(&self) -> Result<Image, ArtError> {
+        self.to_index_image_with(&ArtAtlasSettings::default())
+    }
+
+    pub fn to_index_image_with(&self, settings: &ArtAtlasSettings) -> Result<Image, ArtError> {
+        let atlas = self.pack(settings)?;
+        Ok(Image::new(
+            Extent3d {
+                width: atlas.size.x,
+                height: atlas.size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.index_data(&atlas),
+            TextureFormat::R8Unorm,
+            RenderAssetUsages::all(),
+        ))
+    }
+
+    /// Number of colour tables stored in this art.
+    pub fn palette_count(&self) -> usize {
+        self.color_table_data.len()
+    }
+
+    /// Renders a colour table into a 256x1 image, one pixel per palette index.
+    pub fn to_palette_image(&self, palette: usize) -> Result<Image, ArtError> {
+        Ok(Image::new(
+            Extent3d {
+                width: 256,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.palette_data(palette)?,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::all(),
+        ))
+    }
+
+    /// RGBA bytes of a colour table. Index 0 is always fully transparent.
+    pub fn palette_data(&self, palette: usize) -> Result<Vec<u8>, ArtError> {
+        let color_table = self
+            .color_table_data
+            .get(palette)
+            .ok_or(ArtError::MissingPalette(palette))?;
+        Ok(color_table.to_rgba())
+    }
+
+    fn index_data(&self, atlas: &PackedAtlas) -> Vec<u8> {
+        let width = atlas.size.x as usize;
+        let mut data = vec![0; width * atlas.size.y as usize];
+        for (frame, rect) in self.frame_data.iter().zip(&atlas.rects) {
+            for (y, row) in frame.pixels.iter().enumerate() {
+                let start = (y + rect.min.y as usize) * width + rect.min.x as usize;
+                data[start..start + row.len()].copy_from_slice(row);
+            }
         }
-        TextureAtlasLayout {
-            size: UVec2::new(1, columns),
-            textures,
+        data
+    }
+
+    /// Layout for the image produced by [`Art::to_image`]. The atlas index of a rect is the index of its frame.
+    pub fn to_texture_atlas(&self) -> Result<TextureAtlasLayout, ArtError> {
+        self.to_texture_atlas_with(&ArtAtlasSettings::default())
+    }
+
+    pub fn to_texture_atlas_with(
+        &self,
+        settings: &ArtAtlasSettings,
+    ) -> Result<TextureAtlasLayout, ArtError> {
+        let atlas = self.pack(settings)?;
+        Ok(TextureAtlasLayout {
+            size: atlas.size,
+            textures: atlas.rects,
+        })
+    }
+
+    fn pack(&self, settings: &ArtAtlasSettings) -> Result<PackedAtlas, ArtError> {
+        let sizes: Vec<UVec2> = self
+            .frame_data
+            .iter()
+            .map(|frame| UVec2::new(frame.header.width, frame.header.height))
+            .collect();
+        pack_frames(&sizes, settings)
+    }
+
+    /// Size and anchor points of `frame`.
+    pub fn frame_metrics(&self, frame: usize) -> Result<ArtFrameMetrics, ArtError> {
+        let header = &self
+            .frame_data
+            .get(frame)
+            .ok_or(ArtError::MissingFrame(frame))?
+            .header;
+        Ok(ArtFrameMetrics {
+            size: UVec2::new(header.width, header.height),
+            hotspot: IVec2::new(header.c_x, header.c_y),
+            offset: IVec2::new(header.d_x, header.d_y),
+        })
+    }
+
+    /// Number of frames currently held by this art.
+    pub fn frame_count(&self) -> usize {
+        self.frame_data.len()
+    }
+
+    /// Number of directions an animated art is drawn in, 1 for still images.
+    pub fn rotations(&self) -> u32 {
+        match self.header.animated() {
+            true => 8,
+            false => 1,
+        }
+    }
+
+    /// Number of frames in each rotation.
+    pub fn frames_per_rotation(&self) -> u32 {
+        self.header.frame_num
+    }
+
+    /// Playback speed in frames per second as stored in the header, 0 if the art doesn't specify one.
+    pub fn fps(&self) -> u32 {
+        self.header.fps
+    }
+
+    /// Frame within each rotation that times the action of the animation,
+    /// see [`crate::ArtActionFrame`].
+    pub fn action_frame(&self) -> u32 {
+        self.header.action_frame
+    }
+
+    /// Drops every frame except the ones of `rotation` and, within each rotation, the ones in `frames`.
+    /// Frames of animated arts are stored rotation by rotation, each rotation holding the same number of frames.
+    /// The header follows the kept frames: a single rotation makes the art a still one, the frame count
+    /// shrinks to `frames` and the action frame moves along, or past the last frame if it was dropped.
+    /// Nothing is changed if an error is returned.
+    pub fn retain_frames(
+        &mut self,
+        rotation: Option<u32>,
+        frames: Option<Range<u32>>,
+    ) -> Result<(), ArtError> {
+        let rotations = match rotation {
+            Some(rotation) if rotation >= self.rotations() => {
+                return Err(ArtError::MissingRotation(rotation));
+            }
+            Some(rotation) => rotation..rotation + 1,
+            None => 0..self.rotations(),
+        };
+        let per_rotation = self.frames_per_rotation();
+        let frames = frames.unwrap_or(0..per_rotation);
+        let end = frames.end.min(per_rotation);
+        let frames = frames.start.min(end)..end;
+        if frames.is_empty() {
+            return Err(ArtError::EmptyFrame);
+        }
+
+        let mut index = 0;
+        self.frame_data.retain(|_| {
+            let retained = rotations.contains(&(index / per_rotation))
+                && frames.contains(&(index % per_rotation));
+            index += 1;
+            retained
+        });
+        if rotation.is_some() {
+            self.header.flags |= 0x1;
         }
+        self.header.frame_num = frames.len() as u32;
+        self.header.action_frame = match frames.contains(&self.header.action_frame) {
+            true => self.header.action_frame - frames.start,
+            false => self.header.frame_num,
+        };
+        Ok(())
     }
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct ArtHeader {
-    h0: [u32; 3],
+    /// Bit 0 is set for still images, animated arts have 8 rotations.
+    flags: u32,
+    /// Playback speed of the animation in frames per second.
+    fps: u32,
+    bpp: u32,
     stupid_color: [Color; 4],
 
-    frame_num_low: u32,
+    /// Frame within each rotation at which the animation does its thing,
+    /// e.g. the hit of an attack, the release of a spell or a footstep.
+    action_frame: u32,
+    /// Frames per rotation.
     frame_num: u32,
     palette_data1: ColorPalette,
     palette_data2: ColorPalette,
@@ -181,22 +323,24 @@ pub struct ArtHeader {
 impl ArtHeader {
     const SIZE: usize = 132;
     fn from_buffer(buffer: &[u8]) -> Result<ArtHeader, ArtError> {
-        let h1 = u32::from_le_bytes(buffer[0..4].try_into()?);
-        let h2 = u32::from_le_bytes(buffer[4..8].try_into()?);
-        let h3 = u32::from_le_bytes(buffer[8..12].try_into()?);
+        let flags = u32::from_le_bytes(buffer[0..4].try_into()?);
+        let fps = u32::from_le_bytes(buffer[4..8].try_into()?);
+        let bpp = u32::from_le_bytes(buffer[8..12].try_into()?);
         let stupid_color0 = Color::from_buffer(&buffer[12..16])?;
         let stupid_color1 = Color::from_buffer(&buffer[16..20])?;
         let stupid_color2 = Color::from_buffer(&buffer[20..24])?;
         let stupid_color3 = Color::from_buffer(&buffer[24..28])?;
-        let frame_num_low = u32::from_le_bytes(buffer[28..32].try_into()?);
+        let action_frame = u32::from_le_bytes(buffer[28..32].try_into()?);
         let frame_num = u32::from_le_bytes(buffer[32..36].try_into()?);
         let palette_data1 = ColorPalette::from_buffer(&buffer[36..68])?;
         let palette_data2 = ColorPalette::from_buffer(&buffer[68..100])?;
         let palette_data3 = ColorPalette::from_buffer(&buffer[100..132])?;
         Ok(ArtHeader {
-            h0: [h1, h2, h3],
+            flags,
+            fps,
+            bpp,
             stupid_color: [stupid_color0, stupid_color1, stupid_color2, stupid_color3],
-            frame_num_low,
+            action_frame,
             frame_num,
             palette_data1,
             palette_data2,
@@ -205,7 +349,7 @@ impl ArtHeader {
     }
 
     fn animated(&self) -> bool {
-        self.h0[0] & 0x1 == 0
+        self.flags & 0x1 == 0
     }
 
     fn frames(&self) -> u32 {
@@ -271,6 +415,28 @@ impl ColorTable {
         }
         Ok(ColorTable(colors))
     }
+
+    fn to_rgba(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.0.len() * 4);
+        for (index, color) in self.0.iter().enumerate() {
+            match index {
+                0 => data.extend([0, 0, 0, 0]),
+                _ => data.extend([color.r, color.g, color.b, color.opacity()]),
+            }
+        }
+        data
+    }
+}
+
+/// Placement information of a single frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArtFrameMetrics {
+    pub size: UVec2,
+    /// Point of the frame, measured from its top left corner, that is placed on the position of the object.
+    /// For font glyphs, `y` is the baseline.
+    pub hotspot: IVec2,
+    /// Distance the object moves when this frame is shown, used by walk cycles.
+    pub offset: IVec2,
 }
 
 #[derive(Debug, Deserialize)]
@@ -317,10 +483,10 @@ impl ArtFrame {
                 p += 1;
             }
         } else {
-            for p in 0..self.header.size {
+            for (p, &value) in buffer.iter().enumerate().take(self.header.size) {
                 let x = p % self.header.width as usize;
                 let y = p / self.header.width as usize;
-                self.pixels[y][x] = buffer[p];
+                self.pixels[y][x] = value;
             }
         }
         Ok(())
@@ -329,10 +495,6 @@ impl ArtFrame {
     fn size(&self) -> usize {
         self.header.size
     }
-
-    fn width(&self) -> usize {
-        self.header.width as usize
-    }
 }
 
 struct Counter {
@@ -399,3 +561,94 @@ impl ArtFrameHeader {
         })
     }
 }
+
+/// Builds a minimal uncompressed, non-animated `.ART` buffer running at 10 fps with action frame 1
+/// and a single palette where
+/// colour `i` is `(r: i, g: 255 - i, b: 0)` and every pixel holds `(x + y * width + 1) % 256`.
+#[cfg(test)]
+pub(crate) fn test_art_buffer(frames: &[(u32, u32)]) -> Vec<u8> {
+    let mut buffer = vec![0; ArtHeader::SIZE];
+    buffer[0..4].copy_from_slice(&1u32.to_le_bytes());
+    buffer[4..8].copy_from_slice(&10u32.to_le_bytes());
+    buffer[28..32].copy_from_slice(&1u32.to_le_bytes());
+    buffer[12] = 1;
+    buffer[32..36].copy_from_slice(&(frames.len() as u32).to_le_bytes());
+    for i in 0..256 {
+        buffer.extend([0, 255 - i as u8, i as u8, 0]);
+    }
+    for (width, height) in frames {
+        buffer.extend(width.to_le_bytes());
+        buffer.extend(height.to_le_bytes());
+        buffer.extend((width * height).to_le_bytes());
+        buffer.extend([0; 16]);
+    }
+    for (width, height) in frames {
+        buffer.extend((0..width * height).map(|p| ((p + 1) % 256) as u8));
+    }
+    buffer
+}
+
+#[test]
+fn test_indexed_image_matches_rgba_image() {
+    let art = Art::from_buffer(&test_art_buffer(&[(4, 3), (2, 5)])).unwrap();
+    let layout = art.to_texture_atlas().unwrap();
+    let indices = art.to_index_image().unwrap();
+    let rgba = art.to_image().unwrap();
+    assert_eq!(indices.texture_descriptor.format, TextureFormat::R8Unorm);
+    assert_eq!(indices.size(), layout.size);
+    assert_eq!(rgba.size(), layout.size);
+
+    let indices = indices.data.unwrap();
+    let rgba = rgba.data.unwrap();
+    let palette = art.palette_data(0).unwrap();
+    let second = layout.textures[1];
+    let offset = (second.min.y * layout.size.x + second.min.x + 1) as usize;
+    assert_eq!(indices[offset], 2);
+    for (index, sample) in indices.iter().enumerate() {
+        let color = *sample as usize * 4;
+        assert_eq!(rgba[index * 4..index * 4 + 4], palette[color..color + 4]);
+    }
+}
+
+#[test]
+fn test_palette_data() {
+    let art = Art::from_buffer(&test_art_buffer(&[(1, 1)])).unwrap();
+    assert_eq!(art.palette_count(), 1);
+    let palette = art.palette_data(0).unwrap();
+    assert_eq!(palette.len(), 1024);
+    assert_eq!(palette[0..4], [0, 0, 0, 0]);
+    assert_eq!(palette[40..44], [10, 245, 0, 255]);
+    assert!(matches!(
+        art.palette_data(1),
+        Err(ArtError::MissingPalette(1))
+    ));
+}
+
+#[test]
+fn test_retain_frames() {
+    let mut art = Art::from_buffer(&test_art_buffer(&[(1, 1), (2, 2), (3, 3)])).unwrap();
+    assert!(matches!(
+        art.retain_frames(Some(1), None),
+        Err(ArtError::MissingRotation(1))
+    ));
+    art.retain_frames(Some(0), Some(1..5)).unwrap();
+    assert_eq!(art.frame_count(), 2);
+    assert_eq!((art.frames_per_rotation(), art.action_frame()), (2, 0));
+    assert_eq!(art.frame_image(0, 0).unwrap().size(), UVec2::new(2, 2));
+    assert!(art.retain_frames(None, Some(2..3)).is_err());
+    assert_eq!(art.frame_count(), 2);
+
+    // 8 rotations of 2 frames, frame `f` of rotation `r` is `r + 1` pixels wide and `f + 1` high
+    let sizes: Vec<_> = (1..=8).flat_map(|r| [(r, 1), (r, 2)]).collect();
+    let mut buffer = test_art_buffer(&sizes);
+    buffer[0..4].copy_from_slice(&0u32.to_le_bytes());
+    buffer[32..36].copy_from_slice(&2u32.to_le_bytes());
+    let mut art = Art::from_buffer(&buffer).unwrap();
+    assert_eq!((art.rotations(), art.frames_per_rotation()), (8, 2));
+    art.retain_frames(None, Some(1..2)).unwrap();
+    assert_eq!((art.rotations(), art.frames_per_rotation()), (8, 1));
+    assert_eq!(art.frame_metrics(7).unwrap().size, UVec2::new(8, 2));
+    art.retain_frames(Some(2), None).unwrap();
+    assert_eq!((art.rotations(), art.frames_per_rotation()), (1, 1));
+    assert_eq!(art.frame_metrics(0).unwrap().size, UVec2::new(3, 2));
+}
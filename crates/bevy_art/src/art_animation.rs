@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::Art;
+
+/// Playback speed for arts whose header doesn't specify one.
+pub const DEFAULT_ART_FPS: u32 = 10;
+
+/// Plays one rotation of an animated art on a [`Sprite`] whose texture atlas holds every frame of the art,
+/// e.g. the `#image` and `#layout` sub assets loaded with default settings.
+#[derive(Clone, Component, Debug)]
+#[require(Sprite)]
+pub struct ArtAnimation {
+    pub art: Handle<Art>,
+    pub rotation: u32,
+    /// Current frame within the rotation.
+    pub frame: u32,
+    pub repeat: bool,
+    elapsed: f32,
+    /// Whether the first frame has been shown, which counts as entering it.
+    started: bool,
+}
+
+/// Sent when an [`ArtAnimation`] reaches the action frame stored in its art,
+/// so hits, spell releases and footsteps line up with what is on screen.
+#[derive(Clone, Debug, Event)]
+pub struct ArtActionFrame {
+    pub entity: Entity,
+    pub art: Handle<Art>,
+    pub rotation: u32,
+    pub frame: u32,
+}
+
+impl ArtAnimation {
+    pub fn new(art: Handle<Art>) -> ArtAnimation {
+        ArtAnimation {
+            art,
+            rotation: 0,
+            frame: 0,
+            repeat: true,
+            elapsed: 0.,
+            started: false,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: u32) -> ArtAnimation {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn once(mut self) -> ArtAnimation {
+        self.repeat = false;
+        self
+    }
+
+    /// Advances the animation by `delta` seconds and returns whether the action frame was entered.
+    /// The first tick enters the frame the animation starts on.
+    pub fn tick(&mut self, art: &Art, delta: f32) -> bool {
+        let frames = art.frames_per_rotation();
+        if frames == 0 {
+            return false;
+        }
+        let fps = match art.fps() {
+            0 => DEFAULT_ART_FPS,
+            fps => fps,
+        };
+        let frame_time = 1. / fps as f32;
+        let mut reached_action = !self.started && self.frame == art.action_frame();
+        self.started = true;
+        self.elapsed += delta;
+        while self.elapsed >= frame_time {
+            self.elapsed -= frame_time;
+            if self.frame + 1 < frames {
+                self.frame += 1;
+            } else if self.repeat {
+                self.frame = 0;
+            } else {
+                self.elapsed = 0.;
+                break;
+            }
+            reached_action |= self.frame == art.action_frame();
+        }
+        reached_action
+    }
+
+    /// Index of the current frame in the texture atlas of the whole art.
+    pub fn atlas_index(&self, art: &Art) -> usize {
+        (self.rotation * art.frames_per_rotation() + self.frame) as usize
+    }
+}
+
+pub(crate) fn animate_art(
+    time: Res<Time>,
+    arts: Res<Assets<Art>>,
+    mut query: Query<(Entity, &mut ArtAnimation, &mut Sprite)>,
+    mut events: EventWriter<ArtActionFrame>,
+) {
+    for (entity, mut animation, mut sprite) in &mut query {
+        let Some(art) = arts.get(&animation.art) else {
+            continue;
+        };
+        if animation.tick(art, time.delta_secs()) {
+            events.write(ArtActionFrame {
+                entity,
+                art: animation.art.clone(),
+                rotation: animation.rotation,
+                frame: animation.frame,
+            });
+        }
+        if let Some(atlas) = sprite.texture_atlas.as_mut() {
+            atlas.index = animation.atlas_index(art);
+        }
+    }
+}
+
+#[test]
+fn test_action_frame() {
+    let art = Art::from_buffer(&crate::art::test_art_buffer(&[(1, 1); 3])).unwrap();
+    assert_eq!(art.fps(), 10);
+    assert_eq!(art.action_frame(), 1);
+
+    let mut animation = ArtAnimation::new(Handle::default());
+    assert!(!animation.tick(&art, 0.05));
+    assert!(animation.tick(&art, 0.05));
+    assert_eq!(animation.frame, 1);
+    assert!(!animation.tick(&art, 0.1));
+    assert!(animation.tick(&art, 0.2));
+    assert_eq!(animation.atlas_index(&art), 1);
+
+    let mut animation = ArtAnimation::new(Handle::default()).once();
+    animation.tick(&art, 1.);
+    assert_eq!(animation.frame, 2);
+
+    // starting on the action frame enters it
+    let mut buffer = crate::art::test_art_buffer(&[(1, 1); 3]);
+    buffer[28..32].copy_from_slice(&0u32.to_le_bytes());
+    let art = Art::from_buffer(&buffer).unwrap();
+    let mut animation = ArtAnimation::new(Handle::default()).once();
+    assert!(animation.tick(&art, 0.));
+    assert!(!animation.tick(&art, 0.));
+    assert!(!animation.tick(&art, 1.));
+    let mut animation = ArtAnimation::new(Handle::default());
+    assert!(animation.tick(&art, 0.05));
+    assert!(!animation.tick(&art, 0.1));
+    assert!(animation.tick(&art, 0.2));
+}
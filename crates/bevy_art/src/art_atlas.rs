@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ArtError;
+
+/// Largest texture side we pack into by default. Every desktop GPU supports at least this size,
+/// so an atlas that fits here can be uploaded anywhere.
+pub const DEFAULT_MAX_ATLAS_SIZE: u32 = 4096;
+
+/// Controls how the frames of an art are packed into one texture.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ArtAtlasSettings {
+    /// Maximum width and height of the atlas in pixels.
+    pub max_size: u32,
+    /// Empty pixels between neighbouring frames, so filtering samplers don't bleed into the next frame.
+    pub padding: u32,
+}
+
+impl Default for ArtAtlasSettings {
+    fn default() -> Self {
+        ArtAtlasSettings {
+            max_size: DEFAULT_MAX_ATLAS_SIZE,
+            padding: 0,
+        }
+    }
+}
+
+/// Result of packing a list of frame sizes into one texture.
+/// `rects[i]` always belongs to the i-th frame, so frame indices can be used as atlas indices.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PackedAtlas {
+    pub(crate) size: UVec2,
+    pub(crate) rects: Vec<URect>,
+}
+
+/// Packs frames into rows ("shelves") from left to right and top to bottom.
+/// The atlas starts out roughly square and only gets wider when it would exceed `max_size` in height.
+pub(crate) fn pack_frames(
+    sizes: &[UVec2],
+    settings: &ArtAtlasSettings,
+) -> Result<PackedAtlas, ArtError> {
+    let max_size = settings.max_size;
+    let padding = settings.padding;
+    let widest = sizes
+        .iter()
+        .map(|size| size.x)
+        .max()
+        .ok_or(ArtError::EmptyFrame)?;
+    let tallest = sizes.iter().map(|size| size.y).max().unwrap_or_default();
+    if widest > max_size || tallest > max_size {
+        return Err(ArtError::AtlasTooLarge(max_size));
+    }
+
+    let area: u64 = sizes
+        .iter()
+        .map(|size| (size.x + padding) as u64 * (size.y + padding) as u64)
+        .sum();
+    let mut width = ((area as f64).sqrt().ceil() as u32).clamp(widest, max_size);
+    loop {
+        let packed = pack_shelves(sizes, width, padding);
+        if packed.size.y <= max_size {
+            return Ok(packed);
+        }
+        if width == max_size {
+            return Err(ArtError::AtlasTooLarge(max_size));
+        }
+        width = (width * 2).min(max_size);
+    }
+}
+
+fn pack_shelves(sizes: &[UVec2], width: u32, padding: u32) -> PackedAtlas {
+    let mut rects = Vec::with_capacity(sizes.len());
+    let mut cursor = UVec2::ZERO;
+    let mut shelf_height = 0;
+    let mut used_width = 0;
+    for size in sizes {
+        if cursor.x > 0 && cursor.x + padding + size.x > width {
+            cursor = UVec2::new(0, cursor.y + shelf_height + padding);
+            shelf_height = 0;
+        } else if cursor.x > 0 {
+            cursor.x += padding;
+        }
+        rects.push(URect::from_corners(cursor, cursor + *size));
+        cursor.x += size.x;
+        used_width = used_width.max(cursor.x);
+        shelf_height = shelf_height.max(size.y);
+    }
+    PackedAtlas {
+        size: UVec2::new(used_width, cursor.y + shelf_height),
+        rects,
+    }
+}
+
+#[test]
+fn test_pack_frames_wraps_into_rows() {
+    let sizes = vec![UVec2::new(10, 20); 100];
+    let packed = pack_frames(&sizes, &settings(256, 0)).unwrap();
+    assert_eq!(packed.size, UVec2::new(140, 160));
+    assert_eq!(packed.rects[0], URect::new(0, 0, 10, 20));
+    assert_eq!(packed.rects[15], URect::new(10, 20, 20, 40));
+    for (rect, size) in packed.rects.iter().zip(&sizes) {
+        assert_eq!(rect.size(), *size);
+        assert!(rect.max.x <= packed.size.x && rect.max.y <= packed.size.y);
+    }
+}
+
+#[test]
+fn test_pack_frames_respects_limit() {
+    let packed = pack_frames(&[UVec2::new(64, 64); 16], &settings(256, 0)).unwrap();
+    assert_eq!(packed.size, UVec2::new(256, 256));
+    assert!(pack_frames(&[UVec2::new(64, 64); 17], &settings(256, 0)).is_err());
+    assert!(pack_frames(&[UVec2::new(300, 1)], &settings(256, 0)).is_err());
+}
+
+#[test]
+fn test_pack_frames_with_padding() {
+    let packed = pack_frames(&[UVec2::new(8, 8); 4], &settings(64, 2)).unwrap();
+    assert_eq!(packed.size, UVec2::new(18, 18));
+    assert_eq!(packed.rects[1], URect::new(10, 0, 18, 8));
+    assert_eq!(packed.rects[2], URect::new(0, 10, 8, 18));
+}
+
+#[cfg(test)]
+fn settings(max_size: u32, padding: u32) -> ArtAtlasSettings {
+    ArtAtlasSettings { max_size, padding }
+}
@@ -0,0 +1,623 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const ART_ID_TYPE_SHIFT: u32 = 28;
+
+/// Every kind of art TIG knows about, stored in the upper four bits of a [`TigArtId`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum ArtType {
+    Tile = 0,
+    Wall = 1,
+    Critter = 2,
+    Portal = 3,
+    Scenery = 4,
+    Interface = 5,
+    Item = 6,
+    Container = 7,
+    Misc = 8,
+    Light = 9,
+    Roof = 10,
+    Facade = 11,
+    Monster = 12,
+    UniqueNpc = 13,
+    EyeCandy = 14,
+}
+
+impl ArtType {
+    pub const ALL: [ArtType; 15] = [
+        ArtType::Tile,
+        ArtType::Wall,
+        ArtType::Critter,
+        ArtType::Portal,
+        ArtType::Scenery,
+        ArtType::Interface,
+        ArtType::Item,
+        ArtType::Container,
+        ArtType::Misc,
+        ArtType::Light,
+        ArtType::Roof,
+        ArtType::Facade,
+        ArtType::Monster,
+        ArtType::UniqueNpc,
+        ArtType::EyeCandy,
+    ];
+
+    pub fn blend_mode(self) -> ArtBlendMode {
+        match self {
+            ArtType::Light | ArtType::EyeCandy => ArtBlendMode::Additive,
+            _ => ArtBlendMode::Alpha,
+        }
+    }
+
+    /// Bit layout of the ids of this type:
+    ///
+    /// | type                                  | num    | animation | rotation | frame  | palette |
+    /// |---------------------------------------|--------|-----------|----------|--------|---------|
+    /// | tile, roof                            | 16..28 |           |          |        | 0..2    |
+    /// | wall                                  | 20..28 |           | 11..14   |        | 0..2    |
+    /// | interface                             | 16..28 |           |          | 8..16  | 4..6    |
+    /// | item                                  | 17..28 |           | 11..14   | 4..11  | 0..2    |
+    /// | critter                               | 19..28 | 14..19    | 11..14   | 6..11  | 0..2    |
+    /// | monster, unique npc                   | 19..28 | 14..19    | 11..14   | 4..11  | 0..2    |
+    /// | portal, scenery, container, misc,     | 19..28 |           | 11..14   | 4..11  | 0..2    |
+    /// | light, facade, eye candy              |        |           |          |        |         |
+    ///
+    /// Some types pack more into their ids, see [`TigArtId::tile`], [`TigArtId::wall`],
+    /// [`TigArtId::critter`] and [`TigArtId::interface_flag`]. For critters, `num` holds the body fields
+    /// that pick the art file, the animation picks the action within it.
+    const fn layout(self) -> ArtIdLayout {
+        const PALETTE: Option<ArtIdField> = Some(ArtIdField::new("palette", 0, 2));
+        const FRAME: Option<ArtIdField> = Some(ArtIdField::new("frame", 4, 7));
+        const ROTATION: Option<ArtIdField> = Some(ArtIdField::new("rotation", 11, 3));
+        const ANIMATION: Option<ArtIdField> = Some(ArtIdField::new("animation", 14, 5));
+        match self {
+            ArtType::Tile | ArtType::Roof => ArtIdLayout {
+                num: ArtIdField::new("num", 16, 12),
+                frame: None,
+                rotation: None,
+                palette: PALETTE,
+                animation: None,
+            },
+            ArtType::Wall => ArtIdLayout {
+                num: ArtIdField::new("num", 20, 8),
+                frame: None,
+                rotation: ROTATION,
+                palette: PALETTE,
+                animation: None,
+            },
+            ArtType::Interface => ArtIdLayout {
+                num: ArtIdField::new("num", 16, 12),
+                frame: Some(ArtIdField::new("frame", 8, 8)),
+                rotation: None,
+                palette: Some(ArtIdField::new("palette", 4, 2)),
+                animation: None,
+            },
+            ArtType::Item => ArtIdLayout {
+                num: ArtIdField::new("num", 17, 11),
+                frame: FRAME,
+                rotation: ROTATION,
+                palette: PALETTE,
+                animation: None,
+            },
+            ArtType::Critter => ArtIdLayout {
+                num: ArtIdField::new("num", 19, 9),
+                frame: Some(ArtIdField::new("frame", 6, 5)),
+                rotation: ROTATION,
+                palette: PALETTE,
+                animation: ANIMATION,
+            },
+            ArtType::Monster | ArtType::UniqueNpc => ArtIdLayout {
+                num: ArtIdField::new("num", 19, 9),
+                frame: FRAME,
+                rotation: ROTATION,
+                palette: PALETTE,
+                animation: ANIMATION,
+            },
+            ArtType::Portal
+            | ArtType::Scenery
+            | ArtType::Container
+            | ArtType::Misc
+            | ArtType::Light
+            | ArtType::Facade
+            | ArtType::EyeCandy => ArtIdLayout {
+                num: ArtIdField::new("num", 19, 9),
+                frame: FRAME,
+                rotation: ROTATION,
+                palette: PALETTE,
+                animation: None,
+            },
+        }
+    }
+}
+
+const INTERFACE_FLAG: ArtIdField = ArtIdField::new("flag", 7, 1);
+const TILE_NUM1: ArtIdField = ArtIdField::new("num1", 22, 6);
+const TILE_NUM2: ArtIdField = ArtIdField::new("num2", 16, 6);
+const TILE_VARIATION: ArtIdField = ArtIdField::new("variation", 12, 4);
+const TILE_FLIPPABLE: ArtIdField = ArtIdField::new("flippable", 11, 1);
+const WALL_PIECE: ArtIdField = ArtIdField::new("piece", 14, 6);
+const WALL_VARIATION: ArtIdField = ArtIdField::new("variation", 7, 4);
+const WALL_DAMAGE: ArtIdField = ArtIdField::new("damage", 5, 2);
+const CRITTER_GENDER: ArtIdField = ArtIdField::new("gender", 27, 1);
+const CRITTER_BODY: ArtIdField = ArtIdField::new("body", 24, 3);
+const CRITTER_ARMOUR: ArtIdField = ArtIdField::new("armour", 20, 4);
+const CRITTER_SHIELD: ArtIdField = ArtIdField::new("shield", 19, 1);
+const CRITTER_WEAPON: ArtIdField = ArtIdField::new("weapon", 2, 4);
+
+/// The parts of a tile id, its `num` is `num1` and `num2` together.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TileArtId {
+    /// Entry of the tile name file of the terrain the tile is made of.
+    pub num1: u32,
+    /// Entry of the terrain the tile blends into, the same as `num1` for tiles of a single terrain.
+    pub num2: u32,
+    pub variation: u32,
+    /// Whether the tile may be drawn mirrored to break up repetition.
+    pub flippable: bool,
+}
+
+/// The parts of a wall id besides its rotation and palette.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WallArtId {
+    /// Entry of the wall name file.
+    pub num: u32,
+    /// Piece of the wall, e.g. a straight segment, a corner or one with a window.
+    pub piece: u32,
+    pub variation: u32,
+    /// 0 for intact walls, higher for more damaged ones.
+    pub damage: u32,
+}
+
+/// The body fields that make up the `num` of a critter id, plus the weapon in hand.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CritterArtId {
+    pub gender: u32,
+    pub body: u32,
+    pub armour: u32,
+    pub shield: bool,
+    pub weapon: u32,
+}
+
+/// How the pixels of an art are composited over the scene.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum ArtBlendMode {
+    /// Regular alpha blending, palette index 0 is transparent.
+    #[default]
+    Alpha,
+    /// Colours are added onto what is already drawn, so black pixels disappear.
+    /// Lights and eye candy are drawn like this.
+    Additive,
+}
+
+impl ArtBlendMode {
+    /// Bakes the blend mode into RGBA pixels that are drawn with regular alpha blending, for when
+    /// [`crate::ArtPaletteMaterial`] isn't used. Additive pixels get their brightest channel as alpha
+    /// and their colour scaled up to match, so black disappears. Drawn with alpha blending
+    /// the result approximates additive blending on dark backgrounds, bright backgrounds are darkened slightly.
+    pub fn bake(self, rgba: &mut [u8]) {
+        if self != ArtBlendMode::Additive {
+            return;
+        }
+        for pixel in rgba.chunks_exact_mut(4) {
+            let brightest = pixel[0].max(pixel[1]).max(pixel[2]);
+            if brightest > 0 {
+                for channel in &mut pixel[..3] {
+                    *channel = (*channel as u16 * 255 / brightest as u16) as u8;
+                }
+            }
+            pixel[3] = pixel[3].min(brightest);
+        }
+    }
+}
+
+impl TryFrom<u32> for ArtType {
+    type Error = ArtIdError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        ArtType::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or(ArtIdError::InvalidType(value))
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ArtIdError {
+    #[error("{0} is not a valid art type")]
+    InvalidType(u32),
+    #[error("{field} {value} is out of range, the maximum is {max}")]
+    OutOfRange {
+        field: &'static str,
+        value: u32,
+        max: u32,
+    },
+    #[error("{art_type:?} art ids have no {field}")]
+    UnsupportedField {
+        art_type: ArtType,
+        field: &'static str,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct ArtIdField {
+    name: &'static str,
+    shift: u32,
+    bits: u32,
+}
+
+impl ArtIdField {
+    const fn new(name: &'static str, shift: u32, bits: u32) -> ArtIdField {
+        ArtIdField { name, shift, bits }
+    }
+
+    const fn max(self) -> u32 {
+        (1 << self.bits) - 1
+    }
+
+    const fn get(self, raw: u32) -> u32 {
+        (raw >> self.shift) & self.max()
+    }
+
+    fn set(self, raw: u32, value: u32) -> Result<u32, ArtIdError> {
+        if value > self.max() {
+            return Err(ArtIdError::OutOfRange {
+                field: self.name,
+                value,
+                max: self.max(),
+            });
+        }
+        Ok((raw & !(self.max() << self.shift)) | (value << self.shift))
+    }
+}
+
+struct ArtIdLayout {
+    num: ArtIdField,
+    frame: Option<ArtIdField>,
+    rotation: Option<ArtIdField>,
+    palette: Option<ArtIdField>,
+    animation: Option<ArtIdField>,
+}
+
+/// The 32 bit id TIG uses to reference art. Prototypes, sectors and save files store these.
+/// The upper four bits hold the [`ArtType`], the meaning of the remaining bits depends on it.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Reflect, Serialize,
+)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct TigArtId(u32);
+
+impl TigArtId {
+    /// Creates the id of the first frame, rotation and palette of art `num` of the given type.
+    pub fn new(art_type: ArtType, num: u32) -> Result<TigArtId, ArtIdError> {
+        let raw = (art_type as u32) << ART_ID_TYPE_SHIFT;
+        Ok(TigArtId(art_type.layout().num.set(raw, num)?))
+    }
+
+    /// Checks the type bits of an id read from game data.
+    pub fn from_raw(raw: u32) -> Result<TigArtId, ArtIdError> {
+        ArtType::try_from(raw >> ART_ID_TYPE_SHIFT)?;
+        Ok(TigArtId(raw))
+    }
+
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+
+    pub fn art_type(self) -> ArtType {
+        ArtType::ALL[(self.0 >> ART_ID_TYPE_SHIFT) as usize]
+    }
+
+    /// Number of the art within its type, the key into the type's name `.mes` file.
+    pub fn num(self) -> u32 {
+        self.layout().num.get(self.0)
+    }
+
+    /// Frame within the current rotation, 0 for types without frames.
+    pub fn frame(self) -> u32 {
+        self.get(self.layout().frame)
+    }
+
+    /// Direction the art is drawn in, 0 for types without rotations.
+    pub fn rotation(self) -> u32 {
+        self.get(self.layout().rotation)
+    }
+
+    pub fn palette(self) -> u32 {
+        self.get(self.layout().palette)
+    }
+
+    /// Action of critters, monsters and unique npcs, 0 for all other types.
+    pub fn animation(self) -> u32 {
+        self.get(self.layout().animation)
+    }
+
+    /// Bit 7 of interface ids, passed as the third argument when TIG creates them. False for other types.
+    pub fn interface_flag(self) -> bool {
+        self.art_type() == ArtType::Interface && INTERFACE_FLAG.get(self.0) == 1
+    }
+
+    pub fn tile(self) -> Option<TileArtId> {
+        (self.art_type() == ArtType::Tile).then(|| TileArtId {
+            num1: TILE_NUM1.get(self.0),
+            num2: TILE_NUM2.get(self.0),
+            variation: TILE_VARIATION.get(self.0),
+            flippable: TILE_FLIPPABLE.get(self.0) == 1,
+        })
+    }
+
+    pub fn wall(self) -> Option<WallArtId> {
+        (self.art_type() == ArtType::Wall).then(|| WallArtId {
+            num: self.num(),
+            piece: WALL_PIECE.get(self.0),
+            variation: WALL_VARIATION.get(self.0),
+            damage: WALL_DAMAGE.get(self.0),
+        })
+    }
+
+    pub fn critter(self) -> Option<CritterArtId> {
+        (self.art_type() == ArtType::Critter).then(|| CritterArtId {
+            gender: CRITTER_GENDER.get(self.0),
+            body: CRITTER_BODY.get(self.0),
+            armour: CRITTER_ARMOUR.get(self.0),
+            shield: CRITTER_SHIELD.get(self.0) == 1,
+            weapon: CRITTER_WEAPON.get(self.0),
+        })
+    }
+
+    pub fn with_num(self, num: u32) -> Result<TigArtId, ArtIdError> {
+        Ok(TigArtId(self.layout().num.set(self.0, num)?))
+    }
+
+    pub fn with_frame(self, frame: u32) -> Result<TigArtId, ArtIdError> {
+        self.set(self.layout().frame, "frame", frame)
+    }
+
+    pub fn with_rotation(self, rotation: u32) -> Result<TigArtId, ArtIdError> {
+        self.set(self.layout().rotation, "rotation", rotation)
+    }
+
+    pub fn with_palette(self, palette: u32) -> Result<TigArtId, ArtIdError> {
+        self.set(self.layout().palette, "palette", palette)
+    }
+
+    pub fn with_animation(self, animation: u32) -> Result<TigArtId, ArtIdError> {
+        self.set(self.layout().animation, "animation", animation)
+    }
+
+    pub fn with_interface_flag(self, flag: bool) -> Result<TigArtId, ArtIdError> {
+        let field = (self.art_type() == ArtType::Interface).then_some(INTERFACE_FLAG);
+        self.set(field, "flag", flag as u32)
+    }
+
+    pub fn with_tile(self, tile: TileArtId) -> Result<TigArtId, ArtIdError> {
+        let raw = self.sub_fields(
+            ArtType::Tile,
+            [
+                (TILE_NUM1, tile.num1),
+                (TILE_NUM2, tile.num2),
+                (TILE_VARIATION, tile.variation),
+                (TILE_FLIPPABLE, tile.flippable as u32),
+            ],
+        )?;
+        Ok(TigArtId(raw))
+    }
+
+    pub fn with_wall(self, wall: WallArtId) -> Result<TigArtId, ArtIdError> {
+        let raw = self.sub_fields(
+            ArtType::Wall,
+            [
+                (self.layout().num, wall.num),
+                (WALL_PIECE, wall.piece),
+                (WALL_VARIATION, wall.variation),
+                (WALL_DAMAGE, wall.damage),
+            ],
+        )?;
+        Ok(TigArtId(raw))
+    }
+
+    pub fn with_critter(self, critter: CritterArtId) -> Result<TigArtId, ArtIdError> {
+        let raw = self.sub_fields(
+            ArtType::Critter,
+            [
+                (CRITTER_GENDER, critter.gender),
+                (CRITTER_BODY, critter.body),
+                (CRITTER_ARMOUR, critter.armour),
+                (CRITTER_SHIELD, critter.shield as u32),
+                (CRITTER_WEAPON, critter.weapon),
+            ],
+        )?;
+        Ok(TigArtId(raw))
+    }
+
+    fn layout(self) -> ArtIdLayout {
+        self.art_type().layout()
+    }
+
+    fn get(self, field: Option<ArtIdField>) -> u32 {
+        field.map_or(0, |field| field.get(self.0))
+    }
+
+    fn set(
+        self,
+        field: Option<ArtIdField>,
+        name: &'static str,
+        value: u32,
+    ) -> Result<TigArtId, ArtIdError> {
+        let Some(field) = field else {
+            return Err(ArtIdError::UnsupportedField {
+                art_type: self.art_type(),
+                field: name,
+            });
+        };
+        Ok(TigArtId(field.set(self.0, value)?))
+    }
+
+    /// Sets the fields of a type specific part of the id, all or none of them.
+    fn sub_fields<const N: usize>(
+        self,
+        art_type: ArtType,
+        fields: [(ArtIdField, u32); N],
+    ) -> Result<u32, ArtIdError> {
+        if self.art_type() != art_type {
+            return Err(ArtIdError::UnsupportedField {
+                art_type: self.art_type(),
+                field: fields[0].0.name,
+            });
+        }
+        fields
+            .into_iter()
+            .try_fold(self.0, |raw, (field, value)| field.set(raw, value))
+    }
+}
+
+impl TryFrom<u32> for TigArtId {
+    type Error = ArtIdError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        TigArtId::from_raw(value)
+    }
+}
+
+impl From<TigArtId> for u32 {
+    fn from(value: TigArtId) -> Self {
+        value.0
+    }
+}
+
+#[test]
+fn test_interface() {
+    let art_id = TigArtId::new(ArtType::Interface, 327)
+        .and_then(|id| id.with_frame(3))
+        .and_then(|id| id.with_interface_flag(true))
+        .and_then(|id| id.with_palette(1))
+        .unwrap();
+    assert_eq!(art_id.raw(), 0x5147_0390);
+    assert_eq!(art_id.art_type(), ArtType::Interface);
+    assert_eq!(art_id.num(), 327);
+    assert_eq!(art_id.frame(), 3);
+    assert!(art_id.interface_flag());
+    assert_eq!(art_id.palette(), 1);
+    assert!(matches!(
+        art_id.with_rotation(1),
+        Err(ArtIdError::UnsupportedField { .. })
+    ));
+}
+
+#[test]
+fn test_decode_raw_ids() {
+    // raw id, type, num, animation, rotation, frame, palette
+    let ids = [
+        (0x00C5_2800, ArtType::Tile, 197, 0, 0, 0, 0),
+        (0x1073_1921, ArtType::Wall, 7, 0, 3, 0, 1),
+        (0x2A49_724C, ArtType::Critter, 329, 5, 6, 9, 0),
+        (0x3020_1031, ArtType::Portal, 4, 0, 2, 3, 1),
+        (0x4060_1031, ArtType::Scenery, 12, 0, 2, 3, 1),
+        (0x5147_0390, ArtType::Interface, 327, 0, 0, 3, 1),
+        (0x6578_0872, ArtType::Item, 700, 0, 1, 7, 2),
+        (0x70F0_1031, ArtType::Container, 30, 0, 2, 3, 1),
+        (0x8010_1031, ArtType::Misc, 2, 0, 2, 3, 1),
+        (0x9028_1031, ArtType::Light, 5, 0, 2, 3, 1),
+        (0xA021_0001, ArtType::Roof, 33, 0, 0, 0, 1),
+        (0xB008_1031, ArtType::Facade, 1, 0, 2, 3, 1),
+        (0xC140_F8B0, ArtType::Monster, 40, 3, 7, 11, 0),
+        (0xD140_F8B0, ArtType::UniqueNpc, 40, 3, 7, 11, 0),
+        (0xE060_1031, ArtType::EyeCandy, 12, 0, 2, 3, 1),
+    ];
+    for (raw, art_type, num, animation, rotation, frame, palette) in ids {
+        let art_id = TigArtId::from_raw(raw).unwrap();
+        assert_eq!(art_id.art_type(), art_type);
+        assert_eq!(
+            [
+                art_id.num(),
+                art_id.animation(),
+                art_id.rotation(),
+                art_id.frame(),
+                art_id.palette()
+            ],
+            [num, animation, rotation, frame, palette],
+            "{art_type:?}"
+        );
+    }
+}
+
+#[test]
+fn test_sub_fields() {
+    let tile = TigArtId::from_raw(0x00C5_2800).unwrap().tile();
+    assert_eq!(
+        tile,
+        Some(TileArtId {
+            num1: 3,
+            num2: 5,
+            variation: 2,
+            flippable: true,
+        })
+    );
+    let wall = WallArtId {
+        num: 7,
+        piece: 12,
+        variation: 2,
+        damage: 1,
+    };
+    assert_eq!(TigArtId::from_raw(0x1073_1921).unwrap().wall(), Some(wall));
+    let critter = CritterArtId {
+        gender: 1,
+        body: 2,
+        armour: 4,
+        shield: true,
+        weapon: 3,
+    };
+    let art_id = TigArtId::from_raw(0x2A49_724C).unwrap();
+    assert_eq!(art_id.critter(), Some(critter));
+    assert_eq!(art_id.wall(), None);
+
+    let encoded = TigArtId::new(ArtType::Critter, 0)
+        .and_then(|id| id.with_critter(critter))
+        .and_then(|id| id.with_animation(5))
+        .and_then(|id| id.with_rotation(6))
+        .and_then(|id| id.with_frame(9))
+        .unwrap();
+    assert_eq!(encoded.raw(), 0x2A49_724C);
+    assert!(
+        TigArtId::new(ArtType::Tile, 0)
+            .unwrap()
+            .with_wall(wall)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_blend_mode() {
+    let additive: Vec<_> = ArtType::ALL
+        .into_iter()
+        .filter(|art_type| art_type.blend_mode() == ArtBlendMode::Additive)
+        .collect();
+    assert_eq!(additive, [ArtType::Light, ArtType::EyeCandy]);
+
+    let pixels = [
+        0, 0, 0, 255, 255, 128, 0, 255, 20, 40, 10, 255, 90, 90, 90, 0,
+    ];
+    let mut alpha = pixels;
+    ArtBlendMode::Alpha.bake(&mut alpha);
+    assert_eq!(alpha, pixels);
+    let mut additive = pixels;
+    ArtBlendMode::Additive.bake(&mut additive);
+    assert_eq!(
+        additive,
+        [
+            0, 0, 0, 0, 255, 128, 0, 255, 127, 255, 63, 40, 255, 255, 255, 0
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_ids() {
+    assert_eq!(
+        TigArtId::from_raw(u32::MAX),
+        Err(ArtIdError::InvalidType(15))
+    );
+    assert!(matches!(
+        TigArtId::new(ArtType::Scenery, 512),
+        Err(ArtIdError::OutOfRange { max: 511, .. })
+    ));
+}
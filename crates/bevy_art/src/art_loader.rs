@@ -1,14 +1,41 @@
+use std::ops::Range;
+
 use bevy::{
     asset::{AssetLoader, LoadContext},
-    image::{Image, TextureAtlasLayout},
+    image::ImageSampler,
 };
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{Art, ArtError};
+use crate::{Art, ArtAtlasSettings, ArtBlendMode, ArtError};
 
+/// Loads an `.ART` file as [`Art`] and adds everything rendered from it as labeled sub assets:
+/// * `#image`: all frames packed into one [`bevy::image::Image`]
+/// * `#layout`: the [`bevy::image::TextureAtlasLayout`] matching `#image`
+/// * `#frame/N`: frame `N` as its own image, only with [`ArtLoaderSettings::frame_images`]
 #[derive(Default)]
 pub(crate) struct ArtLoader;
 
+/// Settings for [`ArtLoader`], usable with `load_with_settings` or in a `.meta` file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ArtLoaderSettings {
+    /// Colour table the frames are rendered with.
+    pub palette: usize,
+    /// Only keep the frames of this rotation of an animated art.
+    pub rotation: Option<u32>,
+    /// Only keep these frames of every rotation.
+    pub frames: Option<Range<u32>>,
+    pub atlas: ArtAtlasSettings,
+    /// Baked into `#image` and `#frame/N`, see [`ArtBlendMode::bake`]. Lights and eye candy
+    /// need [`crate::ArtType::blend_mode`] to not show black boxes around them, which the
+    /// settings `DatRepo::load_art_id` returns for an art id already have.
+    pub blend_mode: ArtBlendMode,
+    /// Also add every frame as its own `#frame/N` image, on top of the atlas.
+    pub frame_images: bool,
+    /// Sampler of `#image` and `#frame/N`.
+    pub sampler: ImageSampler,
+}
+
 #[derive(Debug, Error)]
 pub enum ArtLoaderError {
     #[error("Could not read file")]
@@ -19,68 +46,39 @@ pub enum ArtLoaderError {
 
 impl AssetLoader for ArtLoader {
     type Asset = Art;
-    type Settings = ();
-    type Error = ArtLoaderError;
-
-    async fn load(
-        &self,
-        reader: &mut dyn bevy::asset::io::Reader,
-        _settings: &Self::Settings,
-        _load_context: &mut LoadContext<'_>,
-    ) -> Result<Self::Asset, Self::Error> {
-        let mut bytes = Vec::new();
-        reader.read_to_end(&mut bytes).await?;
-        Ok(Art::from_buffer(&bytes)?)
-    }
-
-    fn extensions(&self) -> &[&str] {
-        &["art", "ART"]
-    }
-}
-
-#[derive(Default)]
-pub(crate) struct ArtImageLoader;
-
-impl AssetLoader for ArtImageLoader {
-    type Asset = Image;
-    type Settings = ();
+    type Settings = ArtLoaderSettings;
     type Error = ArtLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
-        _settings: &Self::Settings,
-        _load_context: &mut LoadContext<'_>,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let art = Art::from_buffer(&bytes)?;
-        Ok(art.to_image()?)
-    }
-
-    fn extensions(&self) -> &[&str] {
-        &["art", "ART"]
-    }
-}
-
-#[derive(Default)]
-pub(crate) struct ArtTextureAtlasLayoutLoader;
-
-impl AssetLoader for ArtTextureAtlasLayoutLoader {
-    type Asset = TextureAtlasLayout;
-    type Settings = ();
-    type Error = ArtLoaderError;
+        let mut art = Art::from_buffer(&bytes)?;
+        art.retain_frames(settings.rotation, settings.frames.clone())?;
 
-    async fn load(
-        &self,
-        reader: &mut dyn bevy::asset::io::Reader,
-        _settings: &Self::Settings,
-        _load_context: &mut LoadContext<'_>,
-    ) -> Result<Self::Asset, Self::Error> {
-        let mut bytes = Vec::new();
-        reader.read_to_end(&mut bytes).await?;
-        let art = Art::from_buffer(&bytes)?;
-        Ok(art.to_texture_atlas())
+        let mut image = art.to_image_with(settings.palette, &settings.atlas)?;
+        image.sampler = settings.sampler.clone();
+        settings
+            .blend_mode
+            .bake(image.data.as_deref_mut().unwrap_or_default());
+        load_context.add_labeled_asset("image".into(), image);
+        load_context
+            .add_labeled_asset("layout".into(), art.to_texture_atlas_with(&settings.atlas)?);
+        if settings.frame_images {
+            for frame in 0..art.frame_count() {
+                let mut image = art.frame_image(frame, settings.palette)?;
+                image.sampler = settings.sampler.clone();
+                settings
+                    .blend_mode
+                    .bake(image.data.as_deref_mut().unwrap_or_default());
+                load_context.add_labeled_asset(format!("frame/{frame}"), image);
+            }
+        }
+        Ok(art)
     }
 
     fn extensions(&self) -> &[&str] {
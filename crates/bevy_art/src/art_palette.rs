@@ -0,0 +1,239 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_resource::{
+            AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState,
+            RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+    sprite::{AlphaMode2d, Material2d, Material2dKey},
+    ui::UiMaterialKey,
+};
+
+use crate::{Art, ArtBlendMode, ArtError};
+
+pub(crate) const ART_PALETTE_SHADER_PATH: &str = "embedded://bevy_art/art_palette.wgsl";
+pub(crate) const ART_PALETTE_UI_SHADER_PATH: &str = "embedded://bevy_art/art_palette_ui.wgsl";
+
+/// Draws an art from its palette index texture, looking up the colours in a palette texture on the GPU.
+/// Every entity that should switch palettes on its own needs its own material, the textures are shared.
+#[derive(Asset, AsBindGroup, Clone, Debug, TypePath)]
+#[bind_group_data(ArtBlendMode)]
+pub struct ArtPaletteMaterial {
+    /// Part of the index texture that is drawn, as `(min.x, min.y, max.x, max.y)` in uv coordinates.
+    #[uniform(0)]
+    pub frame_rect: Vec4,
+    #[texture(1)]
+    pub indices: Handle<Image>,
+    #[texture(2)]
+    pub palette: Handle<Image>,
+    /// All palettes of the art, [`ArtPalette`] picks one of these.
+    pub palettes: Vec<Handle<Image>>,
+    /// Uv rects of all frames, [`ArtPaletteMaterial::set_frame`] picks one of these.
+    pub frames: Vec<Vec4>,
+    /// Use [`crate::ArtType::blend_mode`] to pick the one the original engine uses for an art.
+    pub blend_mode: ArtBlendMode,
+}
+
+impl ArtPaletteMaterial {
+    /// Uploads the index texture and every palette of `art` and shows its first frame with the first palette.
+    pub fn new(art: &Art, images: &mut Assets<Image>) -> Result<ArtPaletteMaterial, ArtError> {
+        let layout = art.to_texture_atlas()?;
+        let size = layout.size.as_vec2();
+        let frames: Vec<Vec4> = layout
+            .textures
+            .iter()
+            .map(|rect| {
+                let min = rect.min.as_vec2() / size;
+                let max = rect.max.as_vec2() / size;
+                Vec4::new(min.x, min.y, max.x, max.y)
+            })
+            .collect();
+        let palettes = (0..art.palette_count())
+            .map(|palette| Ok(images.add(art.to_palette_image(palette)?)))
+            .collect::<Result<Vec<_>, ArtError>>()?;
+        Ok(ArtPaletteMaterial {
+            frame_rect: frames[0],
+            indices: images.add(art.to_index_image()?),
+            palette: palettes
+                .first()
+                .cloned()
+                .ok_or(ArtError::MissingPalette(0))?,
+            palettes,
+            frames,
+            blend_mode: ArtBlendMode::Alpha,
+        })
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: ArtBlendMode) -> ArtPaletteMaterial {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn set_frame(&mut self, frame: usize) {
+        if let Some(rect) = self.frames.get(frame) {
+            self.frame_rect = *rect;
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: usize) -> Result<(), ArtError> {
+        self.palette = self
+            .palettes
+            .get(palette)
+            .ok_or(ArtError::MissingPalette(palette))?
+            .clone();
+        Ok(())
+    }
+}
+
+/// [`ArtPaletteMaterial`] for UI nodes, e.g. interface art shown with a [`MaterialNode`].
+#[derive(Asset, AsBindGroup, Clone, Debug, TypePath)]
+#[bind_group_data(ArtBlendMode)]
+pub struct ArtPaletteUiMaterial {
+    /// Part of the index texture that is drawn, as `(min.x, min.y, max.x, max.y)` in uv coordinates.
+    #[uniform(0)]
+    pub frame_rect: Vec4,
+    #[texture(1)]
+    pub indices: Handle<Image>,
+    #[texture(2)]
+    pub palette: Handle<Image>,
+    pub palettes: Vec<Handle<Image>>,
+    pub frames: Vec<Vec4>,
+    pub blend_mode: ArtBlendMode,
+}
+
+impl ArtPaletteUiMaterial {
+    pub fn new(art: &Art, images: &mut Assets<Image>) -> Result<ArtPaletteUiMaterial, ArtError> {
+        Ok(ArtPaletteMaterial::new(art, images)?.into())
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: ArtBlendMode) -> ArtPaletteUiMaterial {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn set_frame(&mut self, frame: usize) {
+        if let Some(rect) = self.frames.get(frame) {
+            self.frame_rect = *rect;
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: usize) -> Result<(), ArtError> {
+        self.palette = self
+            .palettes
+            .get(palette)
+            .ok_or(ArtError::MissingPalette(palette))?
+            .clone();
+        Ok(())
+    }
+}
+
+impl From<ArtPaletteMaterial> for ArtPaletteUiMaterial {
+    fn from(material: ArtPaletteMaterial) -> Self {
+        ArtPaletteUiMaterial {
+            frame_rect: material.frame_rect,
+            indices: material.indices,
+            palette: material.palette,
+            palettes: material.palettes,
+            frames: material.frames,
+            blend_mode: material.blend_mode,
+        }
+    }
+}
+
+impl Material2d for ArtPaletteMaterial {
+    fn fragment_shader() -> ShaderRef {
+        ART_PALETTE_SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if key.bind_group_data == ArtBlendMode::Additive {
+            blend_additive(descriptor);
+        }
+        Ok(())
+    }
+}
+
+impl UiMaterial for ArtPaletteUiMaterial {
+    fn fragment_shader() -> ShaderRef {
+        ART_PALETTE_UI_SHADER_PATH.into()
+    }
+
+    fn specialize(descriptor: &mut RenderPipelineDescriptor, key: UiMaterialKey<Self>) {
+        if key.bind_group_data == ArtBlendMode::Additive {
+            blend_additive(descriptor);
+        }
+    }
+}
+
+fn blend_additive(descriptor: &mut RenderPipelineDescriptor) {
+    let targets = descriptor
+        .fragment
+        .iter_mut()
+        .flat_map(|fragment| fragment.targets.iter_mut().flatten());
+    for target in targets {
+        target.blend = Some(BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::SrcAlpha,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent::OVER,
+        });
+    }
+}
+
+impl From<&ArtPaletteMaterial> for ArtBlendMode {
+    fn from(material: &ArtPaletteMaterial) -> Self {
+        material.blend_mode
+    }
+}
+
+impl From<&ArtPaletteUiMaterial> for ArtBlendMode {
+    fn from(material: &ArtPaletteUiMaterial) -> Self {
+        material.blend_mode
+    }
+}
+
+/// Selects which palette of its [`ArtPaletteMaterial`] an entity is drawn with.
+/// Changing it recolours the entity without decoding the art again.
+#[derive(Clone, Component, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ArtPalette(pub usize);
+
+pub(crate) fn apply_art_palette(
+    mut materials: ResMut<Assets<ArtPaletteMaterial>>,
+    query: Query<(&ArtPalette, &MeshMaterial2d<ArtPaletteMaterial>), Changed<ArtPalette>>,
+) {
+    for (palette, material) in &query {
+        if let Some(Err(err)) = materials
+            .get_mut(material)
+            .map(|material| material.set_palette(palette.0))
+        {
+            warn!("{err}");
+        }
+    }
+}
+
+pub(crate) fn apply_art_ui_palette(
+    mut materials: ResMut<Assets<ArtPaletteUiMaterial>>,
+    query: Query<(&ArtPalette, &MaterialNode<ArtPaletteUiMaterial>), Changed<ArtPalette>>,
+) {
+    for (palette, material) in &query {
+        if let Some(Err(err)) = materials
+            .get_mut(material)
+            .map(|material| material.set_palette(palette.0))
+        {
+            warn!("{err}");
+        }
+    }
+}
@@ -0,0 +1,17 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var<uniform> frame_rect: vec4<f32>;
+@group(2) @binding(1) var indices: texture_2d<f32>;
+@group(2) @binding(2) var palette: texture_2d<f32>;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(indices);
+    let uv = mix(frame_rect.xy, frame_rect.zw, mesh.uv);
+    let texel = min(vec2<u32>(uv * vec2<f32>(size)), size - vec2<u32>(1u));
+    let index = u32(round(textureLoad(indices, texel, 0).r * 255.0));
+    if index == 0u {
+        discard;
+    }
+    return textureLoad(palette, vec2<u32>(index, 0u), 0);
+}
@@ -0,0 +1,17 @@
+#import bevy_ui::ui_vertex_output::UiVertexOutput
+
+@group(1) @binding(0) var<uniform> frame_rect: vec4<f32>;
+@group(1) @binding(1) var indices: texture_2d<f32>;
+@group(1) @binding(2) var palette: texture_2d<f32>;
+
+@fragment
+fn fragment(in: UiVertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(indices);
+    let uv = mix(frame_rect.xy, frame_rect.zw, in.uv);
+    let texel = min(vec2<u32>(uv * vec2<f32>(size)), size - vec2<u32>(1u));
+    let index = u32(round(textureLoad(indices, texel, 0).r * 255.0));
+    if index == 0u {
+        discard;
+    }
+    return textureLoad(palette, vec2<u32>(index, 0u), 0);
+}
@@ -1,17 +1,27 @@
-use bevy::prelude::*;
+use bevy::{asset::embedded_asset, prelude::*, sprite::Material2dPlugin};
 
 use crate::{
-    Art,
-    art_loader::{ArtImageLoader, ArtLoader, ArtTextureAtlasLayoutLoader},
+    Art, ArtActionFrame, ArtPalette, ArtPaletteMaterial, ArtPaletteUiMaterial,
+    art_animation::animate_art,
+    art_loader::ArtLoader,
+    art_palette::{apply_art_palette, apply_art_ui_palette},
 };
 
 pub struct ArtPlugin;
 
 impl Plugin for ArtPlugin {
     fn build(&self, app: &mut App) {
+        embedded_asset!(app, "art_palette.wgsl");
+        embedded_asset!(app, "art_palette_ui.wgsl");
         app.init_asset::<Art>()
             .init_asset_loader::<ArtLoader>()
-            .init_asset_loader::<ArtImageLoader>()
-            .init_asset_loader::<ArtTextureAtlasLayoutLoader>();
+            .add_plugins((
+                Material2dPlugin::<ArtPaletteMaterial>::default(),
+                UiMaterialPlugin::<ArtPaletteUiMaterial>::default(),
+            ))
+            .register_type::<ArtPalette>()
+            .add_event::<ArtActionFrame>()
+            .add_systems(Update, animate_art)
+            .add_systems(PostUpdate, (apply_art_palette, apply_art_ui_palette));
     }
 }
@@ -1,4 +1,13 @@
+mod art_animation;
+pub use self::art_animation::{ArtActionFrame, ArtAnimation, DEFAULT_ART_FPS};
+mod art_atlas;
+pub use self::art_atlas::{ArtAtlasSettings, DEFAULT_MAX_ATLAS_SIZE};
+mod art_id;
+pub use self::art_id::*;
 mod art_loader;
+pub use self::art_loader::{ArtLoaderError, ArtLoaderSettings};
+mod art_palette;
+pub use self::art_palette::{ArtPalette, ArtPaletteMaterial, ArtPaletteUiMaterial};
 mod art_plugin;
 pub use self::art_plugin::*;
 mod art;
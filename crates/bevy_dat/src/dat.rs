@@ -78,6 +78,7 @@ impl Dat {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct DatFooter {
     uuid: [u8; 16],
@@ -112,6 +113,7 @@ pub enum DatEntryType {
     Directory,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct DatEntry {
     pub filename: String,
@@ -131,10 +133,10 @@ impl DatEntry {
             .ok_or(DatError::NoFilenameEnd)?;
         let filename = str::from_utf8(&buffer[0..filename_end])?.into();
         let mut values = [0; 6];
-        for i in 0..values.len() {
+        for (i, value) in values.iter_mut().enumerate() {
             let start = i * 4;
             let end = start + 4;
-            values[i] = u32::from_le_bytes(
+            *value = u32::from_le_bytes(
                 buffer[filename_end + 1 + start..filename_end + 1 + end].try_into()?,
             );
         }
@@ -1,13 +1,210 @@
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_art::{Art, ArtError};
+use bevy_mes::{Mes, MesEncoding};
 
-#[derive(Component, Reflect)]
+#[derive(Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct ImageTextFont {
     pub font: Handle<ImageFont>,
+    /// Palette of the font art the text is drawn with, e.g. for highlighted menu entries.
+    pub palette: usize,
+    /// Multiplied with the colours of the font, white keeps them as they are.
+    pub color: Color,
 }
 
+impl ImageTextFont {
+    pub fn new(font: Handle<ImageFont>) -> Self {
+        Self { font, ..default() }
+    }
+
+    pub fn with_palette(mut self, palette: usize) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<Color>) -> Self {
+        self.color = color.into();
+        self
+    }
+}
+
+/// A bitmap font made from an `.ART` file holding one frame per character.
 #[derive(Asset, TypePath)]
 pub struct ImageFont {
-    pub(crate) image: Handle<Image>,
+    /// The glyph sheet once per palette of the font art.
+    pub(crate) images: Vec<Handle<Image>>,
     pub(crate) texture_atlas_layout: Handle<TextureAtlasLayout>,
+    pub(crate) glyphs: HashMap<char, Glyph>,
+    /// Distance from the top of a line to the baseline.
+    pub(crate) ascent: u32,
+    /// Distance from the top of one line to the top of the next one.
+    pub(crate) line_height: u32,
+    /// Drawn in place of characters the font has no glyph for.
+    pub(crate) fallback: Option<char>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Glyph {
+    /// Frame of the character in the font art and index in the texture atlas.
+    pub(crate) index: usize,
+    pub(crate) size: UVec2,
+    /// Point of the glyph that sits on the pen position, `y` being the baseline.
+    pub(crate) hotspot: IVec2,
+}
+
+impl ImageFont {
+    /// Characters of frames 1 to 95 of a font art, frame 0 is unused. This is the table the first version
+    /// of the text rendering used. It follows ASCII from the space on, except that `.` and `,` trade places.
+    pub const ASCII_FRAMES: &str = " !\"#$%&'()*+.-,/0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+    /// Byte the frames after [`ImageFont::ASCII_FRAMES`] are assumed to count from, frame 96 being `0x7F`.
+    const FIRST_BYTE: usize = b' ' as usize - 1;
+
+    /// Maps frames 1 to 95 of a font art to characters through [`ImageFont::ASCII_FRAMES`].
+    pub fn ascii_chars(frame_count: usize) -> Vec<(usize, char)> {
+        Self::ASCII_FRAMES
+            .chars()
+            .enumerate()
+            .map(|(index, char)| (index + 1, char))
+            .take_while(|(frame, _)| *frame < frame_count)
+            .collect()
+    }
+
+    /// Maps the frames of a font art to characters, frames 1 to 95 like [`ImageFont::ascii_chars`].
+    /// Later frames are assumed to follow `codepage` in byte order, which is not verified against the
+    /// font arts yet, so a wrong offset garbles every non-ASCII glyph.
+    /// Frames of control characters and bytes the codepage doesn't define are left out.
+    pub fn codepage_chars(frame_count: usize, codepage: MesEncoding) -> Vec<(usize, char)> {
+        let extended = (Self::ASCII_FRAMES.len() + 1..256 - Self::FIRST_BYTE).filter_map(|frame| {
+            let decoded = codepage.decode(&[(Self::FIRST_BYTE + frame) as u8])?;
+            let mut chars = decoded.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) if !char.is_control() => Some((frame, char)),
+                _ => None,
+            }
+        });
+        Self::ascii_chars(frame_count)
+            .into_iter()
+            .chain(extended.take_while(|(frame, _)| *frame < frame_count))
+            .collect()
+    }
+
+    /// Reads the frames and characters of a glyph map, a `.mes` file with `{frame}{character}` entries.
+    /// Characters may also be written as code points, `{200}{U+00E4}`, e.g. for braces.
+    /// Returns the index of the first entry that isn't a single character.
+    pub fn glyph_map_chars(glyph_map: &Mes) -> Result<Vec<(usize, char)>, u32> {
+        glyph_map
+            .iter()
+            .map(|(frame, text)| {
+                let code_point = text
+                    .strip_prefix("U+")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32);
+                let mut chars = text.chars();
+                match (code_point, chars.next(), chars.next()) {
+                    (Some(char), ..) | (None, Some(char), None) => Ok((frame as usize, char)),
+                    _ => Err(frame),
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn glyphs_from_art(
+        art: &Art,
+        chars: &[(usize, char)],
+    ) -> Result<HashMap<char, Glyph>, ArtError> {
+        let mut glyphs = HashMap::new();
+        for &(frame, char) in chars {
+            let metrics = art.frame_metrics(frame)?;
+            glyphs.insert(
+                char,
+                Glyph {
+                    index: frame,
+                    size: metrics.size,
+                    hotspot: metrics.hotspot,
+                },
+            );
+        }
+        Ok(glyphs)
+    }
+
+    pub(crate) fn new(
+        images: Vec<Handle<Image>>,
+        texture_atlas_layout: Handle<TextureAtlasLayout>,
+        glyphs: HashMap<char, Glyph>,
+    ) -> ImageFont {
+        let ascent = glyphs
+            .values()
+            .map(|glyph| glyph.hotspot.y.max(0) as u32)
+            .max()
+            .unwrap_or_default();
+        let descent = glyphs
+            .values()
+            .map(|glyph| (glyph.size.y as i32 - glyph.hotspot.y).max(0) as u32)
+            .max()
+            .unwrap_or_default();
+        ImageFont {
+            images,
+            texture_atlas_layout,
+            glyphs,
+            ascent,
+            line_height: ascent + descent,
+            fallback: None,
+        }
+    }
+
+    pub fn palette_count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Whether the font has a glyph for `char`, not counting the fallback glyph.
+    pub fn contains(&self, char: char) -> bool {
+        self.glyphs.contains_key(&char)
+    }
+
+    pub(crate) fn glyph(&self, char: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&char)
+            .or_else(|| self.glyphs.get(&self.fallback?))
+    }
+
+    /// Horizontal advance of a space, which fonts don't always draw.
+    pub(crate) fn space_width(&self) -> u32 {
+        self.glyphs
+            .get(&' ')
+            .map_or(self.line_height / 3, |glyph| glyph.size.x)
+    }
+}
+
+#[test]
+fn test_glyph_maps() {
+    let chars = ImageFont::codepage_chars(230, MesEncoding::Windows1252);
+    assert_eq!(chars[0], (1, ' '));
+    for glyph in [
+        (12, '+'),
+        (13, '.'),
+        (14, '-'),
+        (15, ','),
+        (17, '0'),
+        (34, 'A'),
+        (95, '~'),
+    ] {
+        assert!(chars.contains(&glyph), "{glyph:?}");
+    }
+    assert!(chars.contains(&(66, 'a')));
+    assert!(chars.contains(&(197, 'ä')));
+    assert!(chars.contains(&(192, 'ß')));
+    // 0x81 is undefined in Windows-1252
+    assert!(!chars.iter().any(|(frame, _)| *frame == 98));
+    assert_eq!(ImageFont::codepage_chars(230, MesEncoding::Utf8).len(), 95);
+    assert_eq!(ImageFont::ascii_chars(230), chars[..95]);
+    assert_eq!(ImageFont::ascii_chars(14).len(), 13);
+
+    let glyph_map = Mes::from_contents("{1}{ }\n{2}{é}\n{3}{U+007B}").unwrap();
+    assert_eq!(
+        ImageFont::glyph_map_chars(&glyph_map),
+        Ok(vec![(1, ' '), (2, 'é'), (3, '{')])
+    );
+    let glyph_map = Mes::from_contents("{1}{ab}").unwrap();
+    assert_eq!(ImageFont::glyph_map_chars(&glyph_map), Err(1));
 }
@@ -1,44 +1,112 @@
-use bevy::asset::{AssetLoader, LoadedAsset};
-use bevy_art::{Art, ArtError};
+use bevy::asset::{AssetLoader, ReadAssetBytesError};
+use bevy_art::{Art, ArtAtlasSettings, ArtError};
+use bevy_mes::{Mes, MesEncoding, MesError};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::ImageFont;
 
+/// Loads an `.ART` file as [`ImageFont`]. It claims no extension, so `.ART` paths and their labels
+/// stay with the art loader of `bevy_art` and fonts are picked by type, e.g. with `load::<ImageFont>`.
 #[derive(Default)]
 pub(crate) struct ImageFontLoader;
 
+/// Settings for loading an [`ImageFont`], usable with `load_with_settings` or in a `.meta` file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ImageFontLoaderSettings {
+    /// Codepage the frames after the ASCII ones follow, see [`ImageFont::codepage_chars`]. The fonts of
+    /// localized releases use the codepage of their language, but how their frames map to it is not
+    /// verified yet, so only the ASCII frames are used by default, see [`ImageFont::ascii_chars`].
+    pub codepage: Option<MesEncoding>,
+    /// Asset path of a glyph map that replaces the codepage, see [`ImageFont::glyph_map_chars`].
+    pub glyph_map: Option<String>,
+    /// Drawn in place of characters the font has no glyph for, `None` leaves them out.
+    pub fallback: Option<char>,
+}
+
+impl Default for ImageFontLoaderSettings {
+    fn default() -> Self {
+        Self {
+            codepage: None,
+            glyph_map: None,
+            fallback: Some('?'),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub(crate) enum ImageFontLoaderError {
     #[error("Could not read file")]
     Io(#[from] std::io::Error),
     #[error("Could not decode file")]
     Format(#[from] ArtError),
+    #[error("Could not read glyph map: {0}")]
+    GlyphMapIo(#[from] ReadAssetBytesError),
+    #[error("Glyph map {0} is in no known encoding")]
+    GlyphMapEncoding(String),
+    #[error("Could not parse glyph map: {0}")]
+    GlyphMap(#[from] MesError),
+    #[error("Glyph map entry {0} is not a single character")]
+    InvalidGlyph(u32),
 }
 
 impl AssetLoader for ImageFontLoader {
     type Asset = ImageFont;
-    type Settings = ();
+    type Settings = ImageFontLoaderSettings;
     type Error = ImageFontLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
         load_context: &mut bevy::asset::LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
         let art = Art::from_buffer(&bytes)?;
-        let image = art.to_image()?;
-        let texture_atlas = art.to_texture_atlas();
+
+        let chars = match &settings.glyph_map {
+            Some(path) => {
+                let bytes = load_context.read_asset_bytes(path).await?;
+                let contents = MesEncoding::Auto
+                    .decode(&bytes)
+                    .ok_or_else(|| ImageFontLoaderError::GlyphMapEncoding(path.clone()))?;
+                let glyph_map = Mes::parse(&contents, path)?;
+                ImageFont::glyph_map_chars(&glyph_map)
+                    .map_err(ImageFontLoaderError::InvalidGlyph)?
+            }
+            None => match settings.codepage {
+                Some(codepage) => ImageFont::codepage_chars(art.frame_count(), codepage),
+                None => ImageFont::ascii_chars(art.frame_count()),
+            },
+        };
+
+        // the first palette keeps the label fonts had before they supported palettes
+        let images = (0..art.palette_count())
+            .map(|palette| {
+                let label = match palette {
+                    0 => "image".to_string(),
+                    palette => format!("image_palette_{palette}"),
+                };
+                Ok(load_context.add_labeled_asset(
+                    label,
+                    art.to_image_with(palette, &ArtAtlasSettings::default())?,
+                ))
+            })
+            .collect::<Result<_, ArtError>>()?;
+        let texture_atlas = art.to_texture_atlas()?;
+        let glyphs = ImageFont::glyphs_from_art(&art, &chars)?;
         Ok(ImageFont {
-            image: load_context.add_labeled_asset("image".into(), image),
-            texture_atlas_layout: load_context
-                .add_labeled_asset("texture_atlas_layout".into(), texture_atlas),
+            fallback: settings.fallback,
+            ..ImageFont::new(
+                images,
+                load_context.add_labeled_asset("texture_atlas_layout".into(), texture_atlas),
+                glyphs,
+            )
         })
     }
 
     fn extensions(&self) -> &[&str] {
-        &["art", "ART"]
+        &[]
     }
 }
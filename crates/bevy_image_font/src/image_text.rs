@@ -1,8 +1,12 @@
 use bevy::prelude::*;
 
-#[derive(Component, Reflect)]
+use crate::ImageTextLayout;
+
+/// Text drawn with an [`crate::ImageTextFont`] into the [`ImageNode`] of its entity.
+/// It may contain markup to change colour, palette and font within the text, see [`crate::parse_markup`].
+#[derive(Component, Default, Reflect)]
 #[reflect(Component)]
-#[require(ImageNode)]
+#[require(ImageNode, ImageTextLayout)]
 pub struct ImageText(pub String);
 
 impl ImageText {
@@ -10,3 +14,16 @@ impl ImageText {
         Self(text.into())
     }
 }
+
+/// World space counterpart of [`ImageText`], rendered into a [`Sprite`].
+/// Spawn it as a child to have it follow an entity, e.g. for names or barks above characters.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+#[require(Sprite, ImageTextLayout)]
+pub struct ImageText2d(pub String);
+
+impl ImageText2d {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self(text.into())
+    }
+}
@@ -0,0 +1,389 @@
+use std::ops::Range;
+
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+use crate::{ImageFont, ImageText, ImageTextFont};
+
+/// Makes an [`ImageText`] editable. While focused it takes keyboard input, shows a caret
+/// and sends [`ImageTextInputSubmitted`] on enter and [`ImageTextInputCancelled`] on escape.
+/// Only characters its font has glyphs for can be entered. Clicking an input with an
+/// [`Interaction`] focuses it, clicking anywhere else or pressing escape clears the focus.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+#[require(ImageText)]
+pub struct ImageTextInput {
+    value: String,
+    /// Position of the caret in characters.
+    caret: usize,
+    /// Other end of the selection, which spans up to the caret.
+    anchor: Option<usize>,
+    /// Maximum number of characters, e.g. for names that have to fit the character sheet.
+    pub max_length: Option<usize>,
+    pub focused: bool,
+    /// Drawn at the caret position while focused.
+    pub caret_char: char,
+    /// Tint of the selected text.
+    pub selection_color: Color,
+}
+
+impl Default for ImageTextInput {
+    fn default() -> Self {
+        Self {
+            value: String::new(),
+            caret: 0,
+            anchor: None,
+            max_length: None,
+            focused: false,
+            caret_char: '|',
+            selection_color: Color::srgb(1., 0.8, 0.),
+        }
+    }
+}
+
+/// Sent when enter is pressed in a focused [`ImageTextInput`].
+#[derive(Clone, Debug, Event)]
+pub struct ImageTextInputSubmitted {
+    pub entity: Entity,
+    pub value: String,
+}
+
+/// Sent when escape is pressed in a focused [`ImageTextInput`].
+#[derive(Clone, Debug, Event)]
+pub struct ImageTextInputCancelled {
+    pub entity: Entity,
+}
+
+impl ImageTextInput {
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.set_value(value);
+        self
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces the value and puts the caret at its end.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.caret = self.len();
+        self.anchor = None;
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Selected characters, empty ranges count as no selection.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        let range = anchor.min(self.caret)..anchor.max(self.caret);
+        (!range.is_empty()).then_some(range)
+    }
+
+    /// Replaces the selection with the characters of `text` that are `allowed`, as many as `max_length` leaves room for.
+    pub fn insert(&mut self, text: &str, allowed: impl Fn(char) -> bool) {
+        self.delete_selection();
+        let room = self
+            .max_length
+            .map_or(usize::MAX, |max| max.saturating_sub(self.len()));
+        let text: String = text
+            .chars()
+            .filter(|char| !char.is_control() && allowed(*char))
+            .take(room)
+            .collect();
+        let byte = self.byte(self.caret);
+        self.value.insert_str(byte, &text);
+        self.caret += text.chars().count();
+    }
+
+    /// Deletes the selection or the character in front of the caret.
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.caret > 0 {
+            self.caret -= 1;
+            self.value.remove(self.byte(self.caret));
+        }
+    }
+
+    /// Deletes the selection or the character behind the caret.
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.caret < self.len() {
+            self.value.remove(self.byte(self.caret));
+        }
+    }
+
+    /// Moves the caret to `position`, extending the selection instead of clearing it if `select` is set.
+    pub fn move_to(&mut self, position: usize, select: bool) {
+        match select {
+            true => self.anchor = self.anchor.or(Some(self.caret)),
+            false => self.anchor = None,
+        }
+        self.caret = position.min(self.len());
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        match self.selection() {
+            Some(selection) if !select => self.move_to(selection.start, false),
+            _ => self.move_to(self.caret.saturating_sub(1), select),
+        }
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        match self.selection() {
+            Some(selection) if !select => self.move_to(selection.end, false),
+            _ => self.move_to(self.caret + 1, select),
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.caret = self.len();
+    }
+
+    /// The value as [`ImageText`] markup, with caret and selection if focused.
+    pub fn to_markup(&self) -> String {
+        let escape = |text: &str| text.replace('[', "[[");
+        if !self.focused {
+            return escape(&self.value);
+        }
+        let selection = self.selection().unwrap_or(self.caret..self.caret);
+        let [before, selected, after] = [
+            0..selection.start,
+            selection.clone(),
+            selection.end..self.len(),
+        ]
+        .map(|range| escape(&self.value[self.byte(range.start)..self.byte(range.end)]));
+        let caret = escape(&self.caret_char.to_string());
+        let selected = match selected.is_empty() {
+            true => selected,
+            false => format!(
+                "[color={}]{selected}[/color]",
+                self.selection_color.to_srgba().to_hex()
+            ),
+        };
+        match self.caret == selection.start {
+            true => format!("{before}{caret}{selected}{after}"),
+            false => format!("{before}{selected}{caret}{after}"),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    fn byte(&self, position: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(position)
+            .map_or(self.value.len(), |(byte, _)| byte)
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let Some(selection) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        let bytes = self.byte(selection.start)..self.byte(selection.end);
+        self.value.replace_range(bytes, "");
+        self.caret = selection.start;
+        self.anchor = None;
+        true
+    }
+}
+
+/// Systems that focus and edit [`ImageTextInput`]s. Systems handling global hotkeys should
+/// run before them and skip keys while [`text_input_focused`], so the escape that clears
+/// the focus isn't handled twice.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ImageTextInputSystems;
+
+/// Whether an [`ImageTextInput`] has the keyboard focus. Usable as a run condition.
+pub fn text_input_focused(inputs: Query<&ImageTextInput>) -> bool {
+    inputs.iter().any(|input| input.focused)
+}
+
+pub(crate) fn focus_clicked_inputs(
+    mouse: Res<ButtonInput<MouseButton>>,
+    clicked: Query<(Entity, &Interaction), Changed<Interaction>>,
+    mut inputs: Query<(Entity, &mut ImageTextInput)>,
+) {
+    let focus = clicked
+        .iter()
+        .find(|(entity, interaction)| {
+            **interaction == Interaction::Pressed && inputs.contains(*entity)
+        })
+        .map(|(entity, _)| entity);
+    if focus.is_none() && !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    for (entity, mut input) in &mut inputs {
+        let focused = Some(entity) == focus;
+        if input.focused != focused {
+            input.focused = focused;
+        }
+    }
+}
+
+pub(crate) fn edit_focused_inputs(
+    mut keyboard: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    image_fonts: Res<Assets<ImageFont>>,
+    mut query: Query<(Entity, &mut ImageTextInput, &ImageTextFont)>,
+    mut submitted: EventWriter<ImageTextInputSubmitted>,
+    mut cancelled: EventWriter<ImageTextInputCancelled>,
+) {
+    let pressed: Vec<_> = keyboard
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .map(|event| event.logical_key.clone())
+        .collect();
+    if pressed.is_empty() {
+        return;
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let control = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+
+    for (entity, mut input, text_font) in &mut query {
+        if !input.focused {
+            continue;
+        }
+        let font = image_fonts.get(&text_font.font);
+        let allowed = |char: char| char == ' ' || font.is_some_and(|font| font.contains(char));
+        for key in &pressed {
+            match key {
+                Key::Character(text) if control && text.eq_ignore_ascii_case("a") => {
+                    input.select_all();
+                }
+                Key::Character(_) if control => {}
+                Key::Character(text) => input.insert(text, allowed),
+                Key::Space => input.insert(" ", allowed),
+                Key::Backspace => input.backspace(),
+                Key::Delete => input.delete(),
+                Key::ArrowLeft => input.move_left(shift),
+                Key::ArrowRight => input.move_right(shift),
+                Key::Home => input.move_to(0, shift),
+                Key::End => input.move_to(usize::MAX, shift),
+                Key::Enter => {
+                    submitted.write(ImageTextInputSubmitted {
+                        entity,
+                        value: input.value.clone(),
+                    });
+                }
+                Key::Escape => {
+                    cancelled.write(ImageTextInputCancelled { entity });
+                    input.focused = false;
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub(crate) fn show_input_text(
+    mut query: Query<(&ImageTextInput, &mut ImageText), Changed<ImageTextInput>>,
+) {
+    for (input, mut image_text) in &mut query {
+        image_text.0 = input.to_markup();
+    }
+}
+
+#[test]
+fn test_editing() {
+    let mut input = ImageTextInput::default().with_max_length(6);
+    let allowed = |char: char| char != 'x';
+    input.insert("Väterx chen", allowed);
+    assert_eq!((input.value(), input.caret()), ("Väter ", 6));
+
+    input.backspace();
+    input.move_left(false);
+    input.move_left(true);
+    input.move_left(true);
+    assert_eq!(input.selection(), Some(2..4));
+    input.insert("[", allowed);
+    assert_eq!(input.value(), "Vä[r");
+
+    input.focused = true;
+    assert_eq!(input.to_markup(), "Vä[[|r");
+    input.move_to(0, true);
+    assert_eq!(input.to_markup(), "|[color=#FFCC00]Vä[[[/color]r");
+
+    input.move_right(false);
+    assert_eq!((input.caret(), input.selection()), (3, None));
+    input.delete();
+    assert_eq!(input.value(), "Vä[");
+}
+
+#[test]
+fn test_focus() {
+    let mut app = App::new();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<Assets<ImageFont>>()
+        .add_event::<KeyboardInput>()
+        .add_event::<ImageTextInputSubmitted>()
+        .add_event::<ImageTextInputCancelled>()
+        .add_systems(
+            Update,
+            (focus_clicked_inputs, edit_focused_inputs)
+                .chain()
+                .in_set(ImageTextInputSystems),
+        );
+    let input = app
+        .world_mut()
+        .spawn((
+            ImageTextInput::default(),
+            ImageTextFont::default(),
+            Interaction::None,
+        ))
+        .id();
+    let focused = |app: &mut App| app.world().get::<ImageTextInput>(input).unwrap().focused;
+    let click = |app: &mut App, interaction: Interaction| {
+        *app.world_mut().get_mut::<Interaction>(input).unwrap() = interaction;
+        let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+        mouse.clear();
+        mouse.release(MouseButton::Left);
+        mouse.press(MouseButton::Left);
+        app.update();
+    };
+
+    click(&mut app, Interaction::Pressed);
+    assert!(focused(&mut app));
+    // clicking elsewhere
+    click(&mut app, Interaction::None);
+    assert!(!focused(&mut app));
+
+    click(&mut app, Interaction::Pressed);
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .clear();
+    app.world_mut().send_event(KeyboardInput {
+        key_code: KeyCode::Escape,
+        logical_key: Key::Escape,
+        state: ButtonState::Pressed,
+        text: None,
+        repeat: false,
+        window: Entity::PLACEHOLDER,
+    });
+    app.update();
+    assert!(!focused(&mut app));
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .resource_mut::<Events<ImageTextInputCancelled>>()
+            .drain()
+            .count(),
+        1
+    );
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+
+use crate::{ImageFont, image_font::Glyph};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum ImageTextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How the lines of an [`crate::ImageText`] are laid out, all distances are in pixels.
+#[derive(Clone, Component, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct ImageTextLayout {
+    /// Added between two characters, may be negative to tighten the text.
+    pub letter_spacing: i32,
+    /// Added to the width of every space.
+    pub word_spacing: i32,
+    /// Added between two lines.
+    pub line_spacing: i32,
+    /// Wraps lines at spaces so they stay within this width. Words wider than this get a line of their own.
+    pub max_width: Option<u32>,
+    /// Alignment of the lines relative to the widest one.
+    pub align: ImageTextAlign,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct PlacedGlyph {
+    pub(crate) glyph: Glyph,
+    /// Index of the font the glyph is taken from.
+    pub(crate) font: usize,
+    /// Top left corner of the glyph in the rendered text.
+    pub(crate) position: IVec2,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TextLayout {
+    pub(crate) glyphs: Vec<PlacedGlyph>,
+    pub(crate) size: UVec2,
+}
+
+/// A word and the index of the font of the space in front of it.
+type Word<'a> = (usize, &'a [(char, usize)]);
+
+impl ImageTextLayout {
+    /// Places the glyphs of `text`, given as characters with the index of their font in `fonts`.
+    /// Lines end at `\n` and, with a `max_width`, in front of words that don't fit anymore.
+    /// All lines are as high as the highest font needs them to be.
+    /// Characters their font has no glyph for are skipped.
+    pub(crate) fn layout(&self, fonts: &[&ImageFont], text: &[(char, usize)]) -> TextLayout {
+        let glyph = |&(char, font): &(char, usize)| fonts[font].glyph(char);
+        let advance = |glyph: &Glyph| glyph.size.x as i32 + self.letter_spacing;
+        let space = |font: usize| {
+            fonts[font].space_width() as i32 + self.word_spacing + self.letter_spacing
+        };
+        let word_width =
+            |word: &[(char, usize)]| word.iter().filter_map(glyph).map(advance).sum::<i32>();
+
+        let mut lines: Vec<(Vec<Word>, i32)> = Vec::new();
+        for paragraph in text.split(|(char, _)| *char == '\n') {
+            let paragraph = match paragraph.last() {
+                Some(('\r', _)) => &paragraph[..paragraph.len() - 1],
+                _ => paragraph,
+            };
+            let mut words = Vec::new();
+            let mut width = 0;
+            for (separator, word) in split_words(paragraph) {
+                let word_width = word_width(word);
+                let overflows = self
+                    .max_width
+                    .is_some_and(|max| width + space(separator) + word_width > max as i32);
+                if !words.is_empty() && overflows {
+                    lines.push((std::mem::take(&mut words), width));
+                    width = 0;
+                }
+                if !words.is_empty() {
+                    width += space(separator);
+                }
+                width += word_width;
+                words.push((separator, word));
+            }
+            lines.push((words, width));
+        }
+
+        let ascent = fonts.iter().map(|font| font.ascent).max().unwrap_or(0) as i32;
+        let descent = fonts
+            .iter()
+            .map(|font| font.line_height - font.ascent)
+            .max()
+            .unwrap_or(0) as i32;
+        let widest = lines.iter().map(|(_, width)| *width).max().unwrap_or(0);
+        let line_advance = ascent + descent + self.line_spacing;
+        let mut glyphs = Vec::new();
+        for (line, (words, width)) in lines.iter().enumerate() {
+            let mut x = match self.align {
+                ImageTextAlign::Left => 0,
+                ImageTextAlign::Center => (widest - width) / 2,
+                ImageTextAlign::Right => widest - width,
+            };
+            let baseline = line as i32 * line_advance + ascent;
+            for (index, (separator, word)) in words.iter().enumerate() {
+                if index > 0 {
+                    x += space(*separator);
+                }
+                for (char, font) in word.iter() {
+                    let Some(glyph) = fonts[*font].glyph(*char) else {
+                        continue;
+                    };
+                    glyphs.push(PlacedGlyph {
+                        glyph: *glyph,
+                        font: *font,
+                        position: IVec2::new(x - glyph.hotspot.x, baseline - glyph.hotspot.y),
+                    });
+                    x += advance(glyph);
+                }
+            }
+        }
+
+        // glyphs may reach out of their line, e.g. with a negative letter spacing
+        let height = lines.len() as i32 * line_advance - self.line_spacing;
+        let min = glyphs
+            .iter()
+            .fold(IVec2::ZERO, |min, placed| min.min(placed.position));
+        let max = glyphs
+            .iter()
+            .fold(IVec2::new(widest, height), |max, placed| {
+                max.max(placed.position + placed.glyph.size.as_ivec2())
+            });
+        for placed in &mut glyphs {
+            placed.position -= min;
+        }
+        TextLayout {
+            glyphs,
+            size: (max - min).max(IVec2::ZERO).as_uvec2(),
+        }
+    }
+}
+
+fn split_words(paragraph: &[(char, usize)]) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut separator = paragraph.first().map_or(0, |(_, font)| *font);
+    let mut start = 0;
+    for (index, (char, font)) in paragraph.iter().enumerate() {
+        if *char == ' ' {
+            words.push((separator, &paragraph[start..index]));
+            separator = *font;
+            start = index + 1;
+        }
+    }
+    words.push((separator, &paragraph[start..]));
+    words
+}
+
+#[test]
+fn test_layout() {
+    let glyph = |index, width, height| Glyph {
+        index,
+        size: UVec2::new(width, height),
+        hotspot: IVec2::new(0, 6),
+    };
+    let glyphs = [
+        ('a', glyph(1, 4, 6)),
+        ('g', glyph(2, 4, 8)),
+        (' ', glyph(3, 2, 1)),
+    ];
+    let font = ImageFont::new(Vec::new(), Handle::default(), glyphs.into_iter().collect());
+    assert_eq!((font.ascent, font.line_height), (6, 8));
+    let text = |text: &str| text.chars().map(|char| (char, 0)).collect::<Vec<_>>();
+
+    let positions = |layout: &TextLayout| {
+        layout
+            .glyphs
+            .iter()
+            .map(|placed| placed.position.to_array())
+            .collect::<Vec<_>>()
+    };
+    let layout = ImageTextLayout {
+        letter_spacing: 1,
+        ..default()
+    }
+    .layout(&[&font], &text("ag a"));
+    assert_eq!(positions(&layout), [[0, 0], [5, 0], [13, 0]]);
+    assert_eq!(layout.size, UVec2::new(18, 8));
+
+    let layout = ImageTextLayout {
+        max_width: Some(10),
+        align: ImageTextAlign::Right,
+        line_spacing: 2,
+        ..default()
+    }
+    .layout(&[&font], &text("aa a\ng"));
+    assert_eq!(positions(&layout), [[0, 0], [4, 0], [4, 10], [4, 20]]);
+    assert_eq!(layout.size, UVec2::new(8, 28));
+}
@@ -0,0 +1,160 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::ImageFont;
+
+/// Fonts an [`crate::ImageText`] can switch to with `[font=name]`, besides its [`crate::ImageTextFont`].
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ImageTextFonts(pub HashMap<String, Handle<ImageFont>>);
+
+impl ImageTextFonts {
+    pub fn with(mut self, name: impl Into<String>, font: Handle<ImageFont>) -> Self {
+        self.0.insert(name.into(), font);
+        self
+    }
+}
+
+/// A piece of text drawn in one style.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImageTextSpan {
+    pub text: String,
+    pub style: ImageTextStyle,
+}
+
+/// Overrides of the [`crate::ImageTextFont`] for a span, `None` keeps its value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImageTextStyle {
+    /// Name of a font in the [`ImageTextFonts`] of the text.
+    pub font: Option<String>,
+    pub palette: Option<usize>,
+    pub color: Option<Color>,
+}
+
+/// Splits `text` into spans at its markup tags:
+///
+/// - `[color=#ffcc00]...[/color]` tints the text,
+/// - `[palette=1]...[/palette]` draws it with another palette of the font art,
+/// - `[font=name]...[/font]` switches to a font of the [`ImageTextFonts`].
+///
+/// Tags nest, a closing tag restores the value before its opening tag. `[[` is a literal `[`,
+/// anything else in brackets that isn't a valid tag is kept as text, so plain strings stay untouched.
+pub fn parse_markup(text: &str) -> Vec<ImageTextSpan> {
+    let mut spans = Vec::new();
+    let mut fonts = Vec::new();
+    let mut palettes = Vec::new();
+    let mut colors = Vec::new();
+    let mut current = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        current.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("[[") {
+            current.push('[');
+            rest = after;
+            continue;
+        }
+        let tag = rest[1..].find(']').map(|end| &rest[1..=end]);
+        let applied = tag.is_some_and(|tag| {
+            let style = ImageTextStyle {
+                font: fonts.last().cloned(),
+                palette: palettes.last().copied(),
+                color: colors.last().copied(),
+            };
+            let applied = match tag.split_once('=') {
+                Some(("font", name)) if !name.is_empty() => {
+                    fonts.push(name.to_string());
+                    true
+                }
+                Some(("palette", palette)) => palette
+                    .parse()
+                    .map(|palette| palettes.push(palette))
+                    .is_ok(),
+                Some(("color", color)) => Srgba::hex(color)
+                    .map(|color| colors.push(color.into()))
+                    .is_ok(),
+                None => match tag {
+                    "/font" => fonts.pop().is_some(),
+                    "/palette" => palettes.pop().is_some(),
+                    "/color" => colors.pop().is_some(),
+                    _ => false,
+                },
+                _ => false,
+            };
+            if applied && !current.is_empty() {
+                spans.push(ImageTextSpan {
+                    text: std::mem::take(&mut current),
+                    style,
+                });
+            }
+            applied
+        });
+        match tag {
+            Some(tag) if applied => rest = &rest[tag.len() + 2..],
+            _ => {
+                current.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    current.push_str(rest);
+    if !current.is_empty() {
+        spans.push(ImageTextSpan {
+            text: current,
+            style: ImageTextStyle {
+                font: fonts.last().cloned(),
+                palette: palettes.last().copied(),
+                color: colors.last().copied(),
+            },
+        });
+    }
+    spans
+}
+
+#[test]
+fn test_parse_markup() {
+    let span = |text: &str, style| ImageTextSpan {
+        text: text.to_string(),
+        style,
+    };
+    assert_eq!(
+        parse_markup("plain [text]"),
+        [span("plain [text]", default())]
+    );
+
+    let red = Color::from(Srgba::hex("#ff0000").unwrap());
+    assert_eq!(
+        parse_markup("A [color=#ff0000]red [palette=2]word[/palette][/color] [[x]"),
+        [
+            span("A ", default()),
+            span(
+                "red ",
+                ImageTextStyle {
+                    color: Some(red),
+                    ..default()
+                }
+            ),
+            span(
+                "word",
+                ImageTextStyle {
+                    palette: Some(2),
+                    color: Some(red),
+                    ..default()
+                }
+            ),
+            span(" [x]", default()),
+        ]
+    );
+    assert_eq!(
+        parse_markup("[font=title]Arcanum[/font][/font]"),
+        [
+            span(
+                "Arcanum",
+                ImageTextStyle {
+                    font: Some("title".to_string()),
+                    ..default()
+                }
+            ),
+            span("[/font]", default()),
+        ]
+    );
+}
@@ -1,13 +1,20 @@
 use bevy::{
     asset::RenderAssetUsages,
+    ecs::system::SystemParam,
     image::ImageSampler,
-    platform::collections::{HashMap, HashSet},
+    platform::collections::HashSet,
     prelude::*,
     render::render_resource::{Extent3d, TextureDimension, TextureFormat},
 };
-use image::{GenericImage, GenericImageView, ImageBuffer, Rgba};
 
-use crate::{ImageFont, ImageText, ImageTextFont, image_font_loader::ImageFontLoader};
+use crate::{
+    ImageFont, ImageText, ImageText2d, ImageTextFont, ImageTextFonts, ImageTextInput,
+    ImageTextInputCancelled, ImageTextInputSubmitted, ImageTextInputSystems, ImageTextLayout,
+    ImageTextStyle,
+    image_font_loader::ImageFontLoader,
+    image_text_input::{edit_focused_inputs, focus_clicked_inputs, show_input_text},
+    parse_markup,
+};
 
 pub struct ImageTextPlugin;
 
@@ -17,7 +24,22 @@ impl Plugin for ImageTextPlugin {
             .init_asset_loader::<ImageFontLoader>()
             .register_type::<ImageTextFont>()
             .register_type::<ImageText>()
-            .add_systems(PostUpdate, (sync, render_text_to_image_node).chain());
+            .register_type::<ImageText2d>()
+            .register_type::<ImageTextLayout>()
+            .register_type::<ImageTextFonts>()
+            .register_type::<ImageTextInput>()
+            .add_event::<ImageTextInputSubmitted>()
+            .add_event::<ImageTextInputCancelled>()
+            .add_systems(
+                Update,
+                (focus_clicked_inputs, edit_focused_inputs, show_input_text)
+                    .chain()
+                    .in_set(ImageTextInputSystems),
+            )
+            .add_systems(
+                PostUpdate,
+                (sync, (render_text_to_image_node, render_text_to_sprite)).chain(),
+            );
     }
 }
 
@@ -26,11 +48,13 @@ pub enum ImageFontRenderError {
     MissingImageFontAsset,
     MissingTextureAsset,
     MissingLayoutAsset,
+    MissingPalette(usize),
 }
 
+/// Renders texts again once their font is loaded or modified.
 fn sync(
     mut events: EventReader<AssetEvent<ImageFont>>,
-    mut query: Query<(&mut ImageText, &ImageTextFont)>,
+    mut query: Query<(&mut ImageTextFont, Option<&ImageTextFonts>)>,
 ) {
     let mut changed_fonts = HashSet::new();
     for id in events.read().filter_map(extract_asset_id) {
@@ -38,9 +62,12 @@ fn sync(
         changed_fonts.insert(id);
     }
 
-    for (mut image_text, text_font) in &mut query {
-        if changed_fonts.contains(&text_font.font.id()) {
-            image_text.set_changed();
+    for (mut text_font, fonts) in &mut query {
+        let mut span_fonts = fonts.into_iter().flat_map(|fonts| fonts.0.values());
+        if changed_fonts.contains(&text_font.font.id())
+            || span_fonts.any(|font| changed_fonts.contains(&font.id()))
+        {
+            text_font.set_changed();
         }
     }
 }
@@ -52,100 +79,229 @@ fn extract_asset_id(event: &AssetEvent<ImageFont>) -> Option<AssetId<ImageFont>>
     }
 }
 
-const STRING: &str = "  !\"#$%&'()*+.-,/0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+type TextToRender<'a, Text, Target> = (
+    Entity,
+    &'a Text,
+    &'a ImageTextFont,
+    Option<&'a ImageTextFonts>,
+    &'a ImageTextLayout,
+    Option<&'a ImageTextTarget>,
+    &'a mut Target,
+);
+type ChangedText<Text> = Or<(
+    Changed<Text>,
+    Changed<ImageTextFont>,
+    Changed<ImageTextFonts>,
+    Changed<ImageTextLayout>,
+)>;
+
+/// The image a text renders into. It is created with the first render
+/// and rewritten in place afterwards, so changing text doesn't allocate new assets.
+#[derive(Component)]
+pub(crate) struct ImageTextTarget(Handle<Image>);
 
 fn render_text_to_image_node(
-    image_fonts: Res<Assets<ImageFont>>,
-    mut images: ResMut<Assets<Image>>,
-    texture_atlas_layouts: Res<Assets<TextureAtlasLayout>>,
-    mut query: Query<(&ImageText, &ImageTextFont, &mut ImageNode), Changed<ImageText>>,
+    mut renderer: TextRenderer,
+    mut query: Query<TextToRender<ImageText, ImageNode>, ChangedText<ImageText>>,
+) {
+    for (entity, image_text, text_font, fonts, layout, target, mut image_node) in &mut query {
+        if let Some(image) =
+            renderer.render(entity, &image_text.0, text_font, fonts, layout, target)
+        {
+            image_node.image = image;
+        }
+    }
+}
+
+fn render_text_to_sprite(
+    mut renderer: TextRenderer,
+    mut query: Query<TextToRender<ImageText2d, Sprite>, ChangedText<ImageText2d>>,
 ) {
-    let mut char_to_coord = HashMap::new();
-    for (index, char) in STRING.chars().enumerate() {
-        char_to_coord.insert(char, index);
+    for (entity, image_text, text_font, fonts, layout, target, mut sprite) in &mut query {
+        if let Some(image) =
+            renderer.render(entity, &image_text.0, text_font, fonts, layout, target)
+        {
+            sprite.image = image;
+        }
     }
-    for (image_text, image_text_font, mut image_node) in &mut query {
-        // fetch assets
-        let (_image_font, font_spritesheet, texture_atlas_layout) = match fetch_assets(
-            &image_text_font,
-            &image_fonts,
-            &images,
-            &texture_atlas_layouts,
+}
+
+/// Renders the text of UI nodes and sprites alike.
+#[derive(SystemParam)]
+struct TextRenderer<'w, 's> {
+    commands: Commands<'w, 's>,
+    image_fonts: Res<'w, Assets<ImageFont>>,
+    images: ResMut<'w, Assets<Image>>,
+    texture_atlas_layouts: Res<'w, Assets<TextureAtlasLayout>>,
+}
+
+impl TextRenderer<'_, '_> {
+    /// Renders `text` into the image of `target`. Without a target a new image is created
+    /// and returned for the caller to display. Texts that can't be rendered yet are skipped.
+    fn render(
+        &mut self,
+        entity: Entity,
+        text: &str,
+        text_font: &ImageTextFont,
+        fonts: Option<&ImageTextFonts>,
+        layout: &ImageTextLayout,
+        target: Option<&ImageTextTarget>,
+    ) -> Option<Handle<Image>> {
+        let (size, data) = match render_text(
+            text,
+            text_font,
+            fonts,
+            layout,
+            &self.image_fonts,
+            &self.images,
+            &self.texture_atlas_layouts,
         ) {
-            Ok(t) => t,
+            Ok(rendered) => rendered,
+            Err(ImageFontRenderError::MissingPalette(palette)) => {
+                warn!("image text {entity} uses palette {palette} its font doesn't have");
+                return None;
+            }
             Err(err) => {
-                error!("{:?}", err);
-                return;
+                // the text renders again once its font is loaded, see `sync`
+                debug!("skipping image text {entity}: {err:?}");
+                return None;
             }
         };
+        let size = Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        };
 
-        // filter string to available chars
-        let filtered = image_text
-            .0
-            .chars()
-            .filter(|character| char_to_coord.contains_key(character));
-
-        // find length of string
-        let mut width = 0;
-        let mut height = 0;
-        for character in filtered {
-            let index = char_to_coord
-                .get(&character)
-                .expect("character filtering guarantees valid characters");
-            let rect = texture_atlas_layout.textures[*index];
-            width += rect.width();
-            height = height.max(rect.height());
+        if let Some(image) = target.and_then(|target| self.images.get_mut(&target.0)) {
+            image.texture_descriptor.size = size;
+            image.data = Some(data);
+            return None;
         }
-        info!("string should be {} by {}", width, height);
-
-        let mut output_image = image::RgbaImage::new(width, height);
-        let data = font_spritesheet.data.as_ref().unwrap();
-        let font_texture: ImageBuffer<Rgba<u8>, _> = ImageBuffer::from_raw(
-            font_spritesheet.width(),
-            font_spritesheet.height(),
-            data.as_slice(),
-        )
-        .unwrap();
-
-        let filtered = image_text
-            .0
-            .chars()
-            .filter(|character| char_to_coord.contains_key(character));
-
-        let mut x_pos = 0;
-        for character in filtered {
-            let index = char_to_coord
-                .get(&character)
-                .expect("character filtering guarantees valid characters");
-            let rect = texture_atlas_layout.textures[*index];
-            let view = font_texture.view(rect.min.x, rect.min.y, rect.width(), rect.height());
-            if let Err(err) = output_image.copy_from(&*view, x_pos, 0) {
-                error!("{:?}", err);
-                return;
-            }
-            x_pos += rect.width();
-        }
-
-        let mut bevy_image = Image::new(
-            Extent3d {
-                width: output_image.width(),
-                height: output_image.height(),
-                depth_or_array_layers: 1,
-            },
+        let mut image = Image::new(
+            size,
             TextureDimension::D2,
-            output_image.into_vec(),
+            data,
             TextureFormat::Rgba8UnormSrgb,
-            RenderAssetUsages::RENDER_WORLD,
+            // kept in the main world as well, so later renders can rewrite it in place
+            RenderAssetUsages::all(),
         );
-        bevy_image.sampler = ImageSampler::nearest();
+        image.sampler = ImageSampler::nearest();
+        let handle = self.images.add(image);
+        self.commands
+            .entity(entity)
+            .insert(ImageTextTarget(handle.clone()));
+        Some(handle)
+    }
+}
 
-        let image_handle = images.add(bevy_image);
-        image_node.image = image_handle;
+/// A font the spans of a text are drawn with.
+#[derive(PartialEq)]
+struct SpanFont<'a> {
+    font: &'a Handle<ImageFont>,
+    palette: usize,
+    color: Color,
+}
+
+impl<'a> SpanFont<'a> {
+    fn resolve(
+        style: &ImageTextStyle,
+        text_font: &'a ImageTextFont,
+        fonts: Option<&'a ImageTextFonts>,
+    ) -> Self {
+        let font = style.font.as_ref().and_then(|name| {
+            let font = fonts.and_then(|fonts| fonts.0.get(name));
+            if font.is_none() {
+                warn!("image text uses font {name} it has no ImageTextFonts entry for");
+            }
+            font
+        });
+        SpanFont {
+            font: font.unwrap_or(&text_font.font),
+            palette: style.palette.unwrap_or(text_font.palette),
+            color: style.color.unwrap_or(text_font.color),
+        }
     }
 }
 
-fn fetch_assets<'assets>(
+/// Renders `text` and its markup to RGBA pixels, at least one pixel large so the image stays valid.
+fn render_text(
+    text: &str,
     text_font: &ImageTextFont,
+    fonts: Option<&ImageTextFonts>,
+    layout: &ImageTextLayout,
+    image_fonts: &Assets<ImageFont>,
+    images: &Assets<Image>,
+    texture_atlas_layouts: &Assets<TextureAtlasLayout>,
+) -> Result<(UVec2, Vec<u8>), ImageFontRenderError> {
+    // the plain font comes first, so empty texts still get the height of a line
+    let mut span_fonts = vec![SpanFont::resolve(&default(), text_font, fonts)];
+    let mut chars = Vec::new();
+    for span in parse_markup(text) {
+        let span_font = SpanFont::resolve(&span.style, text_font, fonts);
+        let index = match span_fonts.iter().position(|font| *font == span_font) {
+            Some(index) => index,
+            None => {
+                span_fonts.push(span_font);
+                span_fonts.len() - 1
+            }
+        };
+        chars.extend(span.text.chars().map(|char| (char, index)));
+    }
+
+    let mut sources = Vec::new();
+    for span_font in &span_fonts {
+        let (image_font, font_sheet, texture_atlas_layout) = fetch_assets(
+            span_font.font,
+            span_font.palette,
+            image_fonts,
+            images,
+            texture_atlas_layouts,
+        )?;
+        let Some(sheet) = font_sheet.data.as_deref() else {
+            return Err(ImageFontRenderError::MissingTextureAsset);
+        };
+        sources.push((
+            image_font,
+            sheet,
+            font_sheet.width() as usize,
+            texture_atlas_layout,
+            span_font.color.to_srgba().to_u8_array(),
+        ));
+    }
+
+    let layout_fonts: Vec<_> = sources.iter().map(|(font, ..)| *font).collect();
+    let text_layout = layout.layout(&layout_fonts, &chars);
+    let size = text_layout.size.max(UVec2::ONE);
+    let mut data = vec![0; (size.x * size.y * 4) as usize];
+    // glyphs may overlap, so only their opaque pixels are copied
+    for placed in &text_layout.glyphs {
+        let (_, sheet, sheet_width, texture_atlas_layout, tint) = sources[placed.font];
+        let Some(rect) = texture_atlas_layout.textures.get(placed.glyph.index) else {
+            continue;
+        };
+        let position = placed.position.as_uvec2();
+        for y in 0..rect.height() {
+            let source_row = (rect.min.y + y) as usize * sheet_width + rect.min.x as usize;
+            let target_row = (position.y + y) as usize * size.x as usize + position.x as usize;
+            for x in 0..rect.width() as usize {
+                let source = &sheet[(source_row + x) * 4..][..4];
+                if source[3] == 0 {
+                    continue;
+                }
+                let target = &mut data[(target_row + x) * 4..][..4];
+                for ((target, source), tint) in target.iter_mut().zip(source).zip(tint) {
+                    *target = (*source as u16 * tint as u16 / 255) as u8;
+                }
+            }
+        }
+    }
+    Ok((size, data))
+}
+
+fn fetch_assets<'assets>(
+    font: &Handle<ImageFont>,
+    palette: usize,
     image_fonts: &'assets Assets<ImageFont>,
     images: &'assets Assets<Image>,
     texture_atlas_layouts: &'assets Assets<TextureAtlasLayout>,
@@ -157,10 +313,13 @@ fn fetch_assets<'assets>(
     ),
     ImageFontRenderError,
 > {
-    let Some(image_font) = image_fonts.get(&text_font.font) else {
+    let Some(image_font) = image_fonts.get(font) else {
         return Err(ImageFontRenderError::MissingImageFontAsset);
     };
-    let Some(image) = images.get(&image_font.image) else {
+    let Some(image) = image_font.images.get(palette) else {
+        return Err(ImageFontRenderError::MissingPalette(palette));
+    };
+    let Some(image) = images.get(image) else {
         return Err(ImageFontRenderError::MissingTextureAsset);
     };
     let Some(texture_atlas_layout) = texture_atlas_layouts.get(&image_font.texture_atlas_layout)
@@ -169,3 +328,128 @@ fn fetch_assets<'assets>(
     };
     Ok((image_font, image, texture_atlas_layout))
 }
+
+/// An app with the text render systems but no renderer, and a font with the 1x1 glyphs `a` and `b`.
+/// Palette 0 draws them white and blue, palette 1 red and half transparent green.
+#[cfg(test)]
+fn test_app() -> (App, Handle<ImageFont>) {
+    let mut app = App::new();
+    app.init_resource::<Assets<Image>>()
+        .init_resource::<Assets<TextureAtlasLayout>>()
+        .init_resource::<Assets<ImageFont>>()
+        .add_event::<AssetEvent<ImageFont>>()
+        .add_systems(
+            PostUpdate,
+            (sync, (render_text_to_image_node, render_text_to_sprite)).chain(),
+        );
+
+    let world = app.world_mut();
+    let sheet = |pixels: [[u8; 4]; 2]| {
+        let size = Extent3d {
+            width: 2,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+        let format = TextureFormat::Rgba8UnormSrgb;
+        Image::new(
+            size,
+            TextureDimension::D2,
+            pixels.concat(),
+            format,
+            default(),
+        )
+    };
+    let mut images = world.resource_mut::<Assets<Image>>();
+    let palettes = vec![
+        images.add(sheet([[255, 255, 255, 255], [0, 0, 255, 255]])),
+        images.add(sheet([[255, 0, 0, 255], [0, 255, 0, 128]])),
+    ];
+    let layout = world
+        .resource_mut::<Assets<TextureAtlasLayout>>()
+        .add(TextureAtlasLayout {
+            size: UVec2::new(2, 1),
+            textures: vec![URect::new(0, 0, 1, 1), URect::new(1, 0, 2, 1)],
+        });
+    let glyph = |index| crate::image_font::Glyph {
+        index,
+        size: UVec2::ONE,
+        hotspot: IVec2::new(0, 1),
+    };
+    let glyphs = [('a', glyph(0)), ('b', glyph(1))].into_iter().collect();
+    let font = ImageFont::new(palettes, layout, glyphs);
+    let font = world.resource_mut::<Assets<ImageFont>>().add(font);
+    (app, font)
+}
+
+#[test]
+fn test_target_image_is_reused() {
+    let (mut app, font) = test_app();
+    let entity = app
+        .world_mut()
+        .spawn((ImageText("ab".into()), ImageTextFont::new(font)))
+        .id();
+    app.update();
+    let image = app.world().get::<ImageNode>(entity).unwrap().image.clone();
+    // the renderer drops images that aren't kept in the main world once they are extracted
+    let usage = app
+        .world()
+        .resource::<Assets<Image>>()
+        .get(&image)
+        .unwrap()
+        .asset_usage;
+    assert!(usage.contains(RenderAssetUsages::MAIN_WORLD));
+
+    app.world_mut().get_mut::<ImageText>(entity).unwrap().0 = "aab".into();
+    app.update();
+    assert_eq!(app.world().get::<ImageNode>(entity).unwrap().image, image);
+    let images = app.world().resource::<Assets<Image>>();
+    assert_eq!(images.len(), 3);
+    assert_eq!(images.get(&image).unwrap().size(), UVec2::new(3, 1));
+}
+
+#[test]
+fn test_render_palette_and_tint() {
+    let (app, font) = test_app();
+    let world = app.world();
+    let render = |text: &str, text_font: &ImageTextFont| {
+        let layout = ImageTextLayout::default();
+        let (image_fonts, images, layouts) = (world.resource(), world.resource(), world.resource());
+        render_text(text, text_font, None, &layout, image_fonts, images, layouts)
+    };
+
+    let plain = ImageTextFont::new(font.clone());
+    let (size, data) = render("ab", &plain).unwrap();
+    assert_eq!(size, UVec2::new(2, 1));
+    assert_eq!(data, [255, 255, 255, 255, 0, 0, 255, 255]);
+
+    // the tint multiplies every channel, alpha included
+    let tinted = ImageTextFont::new(font.clone())
+        .with_palette(1)
+        .with_color(Color::srgba_u8(255, 128, 0, 255));
+    let (_, data) = render("ab", &tinted).unwrap();
+    assert_eq!(data, [255, 0, 0, 255, 0, 128, 0, 128]);
+
+    let (_, data) = render("a[palette=1]b[/palette]", &plain).unwrap();
+    assert_eq!(data, [255, 255, 255, 255, 0, 255, 0, 128]);
+
+    assert!(matches!(
+        render("ab", &ImageTextFont::new(font).with_palette(2)),
+        Err(ImageFontRenderError::MissingPalette(2))
+    ));
+}
+
+#[test]
+fn test_render_to_sprite() {
+    let (mut app, font) = test_app();
+    let entity = app
+        .world_mut()
+        .spawn((ImageText2d("ba".into()), ImageTextFont::new(font)))
+        .id();
+    app.update();
+    let sprite = app.world().get::<Sprite>(entity).unwrap();
+    let image = app.world().resource::<Assets<Image>>().get(&sprite.image);
+    assert_eq!(
+        image.and_then(|image| image.data.as_deref()),
+        Some([0, 0, 255, 255, 255, 255, 255, 255].as_slice())
+    );
+}
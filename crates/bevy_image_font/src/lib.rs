@@ -1,8 +1,18 @@
 mod image_font;
 mod image_font_loader;
 mod image_text;
+mod image_text_input;
+mod image_text_layout;
+mod image_text_markup;
 mod image_text_plugin;
 
 pub use image_font::*;
-pub use image_text::ImageText;
+pub use image_font_loader::ImageFontLoaderSettings;
+pub use image_text::{ImageText, ImageText2d};
+pub use image_text_input::{
+    ImageTextInput, ImageTextInputCancelled, ImageTextInputSubmitted, ImageTextInputSystems,
+    text_input_focused,
+};
+pub use image_text_layout::{ImageTextAlign, ImageTextLayout};
+pub use image_text_markup::*;
 pub use image_text_plugin::*;
@@ -1,6 +1,11 @@
 mod mes;
+mod mes_document;
+mod mes_encoding;
 mod mes_loader;
 mod mes_plugin;
 
-pub use mes::Mes;
+pub use mes::{Mes, MesError, MesSyntaxError};
+pub use mes_document::{MesDocument, MesEntry, MesItem, MesWriteError};
+pub use mes_encoding::MesEncoding;
+pub use mes_loader::{MesLoaderError, MesLoaderSettings};
 pub use mes_plugin::MesPlugin;
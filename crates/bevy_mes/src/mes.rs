@@ -1,49 +1,175 @@
-use std::num::ParseIntError;
+use std::{collections::BTreeMap, ops::RangeBounds};
 
-use bevy::{platform::collections::HashMap, prelude::*};
-use regex::Regex;
+use bevy::prelude::*;
 use thiserror::Error;
 
+use crate::{MesDocument, MesItem};
+
+/// The entries of a `.mes` file ordered by index, for lookups.
+/// Related entries share a range of indices, e.g. descriptions are grouped in blocks of thousands.
 #[derive(Asset, Clone, Debug, TypePath)]
 pub struct Mes {
-    pub contents: HashMap<u32, (Option<String>, String)>,
+    contents: BTreeMap<u32, (Option<String>, String)>,
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, PartialEq)]
 pub enum MesError {
-    #[error("Regex pattern is erroneous")]
-    RegexError(#[from] regex::Error),
-    #[error("could not parse index")]
-    ParseError(#[from] ParseIntError),
+    #[error("{file}:{line}: {kind}")]
+    Syntax {
+        file: String,
+        line: usize,
+        kind: MesSyntaxError,
+    },
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MesSyntaxError {
+    #[error("field is never closed")]
+    UnterminatedField,
+    #[error("'{0}' is not a valid index")]
+    InvalidIndex(String),
+    #[error("entry {0} has no text")]
+    MissingText(u32),
 }
 
 impl Mes {
     pub fn from_contents(raw_content: &str) -> Result<Mes, MesError> {
-        let mut contents = HashMap::new();
-        for line in raw_content.lines() {
-            let pattern = r#"^\{(\d+)\}(.*?)\{(.*?)\}(.*?)$"#;
-            let regex = Regex::new(pattern)?;
-            if let Some(caps) = regex.captures(line) {
-                let index: u32 = caps[1].parse()?;
-                let optional = if let Some(m) = caps.get(2) {
-                    if m.is_empty() {
-                        None
-                    } else {
-                        Some(m.as_str().to_string())
-                    }
-                } else {
-                    None
-                };
-                let content = caps
-                    .get(3)
-                    .map_or(String::new(), |m| m.as_str().to_string());
-                contents.insert(index, (optional, content));
+        Mes::parse(raw_content, "<memory>")
+    }
+
+    /// Parses the contents of a `.mes` file. `file` is only used in error messages.
+    ///
+    /// Every entry is an index in braces followed by its text in braces, e.g. `{100}{Hello}`.
+    /// Text between the two fields, `{100}middle{Hello}`, is kept as optional value.
+    /// Further fields after the text, as in `{100}{Hello}{World}`, are skipped with a warning,
+    /// so are any other characters outside of fields. An entry may follow another on the same line.
+    /// Fields may span several lines and contain balanced braces, `//` starts a comment outside of fields.
+    /// Line breaks in the text are read as `\n`. If an index appears more than once, the last entry wins.
+    pub fn parse(raw_content: &str, file: &str) -> Result<Mes, MesError> {
+        let mut contents = BTreeMap::new();
+        for entry in MesDocument::parse(raw_content, file)?.items {
+            let MesItem::Entry(entry) = entry else {
+                continue;
+            };
+            if contents
+                .insert(entry.index, (entry.middle, entry.text))
+                .is_some()
+            {
+                warn!(
+                    "{file}: duplicate entry {}, keeping the last one",
+                    entry.index
+                );
             }
         }
         Ok(Mes::new(contents))
     }
 
-    fn new(contents: HashMap<u32, (Option<String>, String)>) -> Mes {
+    fn new(contents: BTreeMap<u32, (Option<String>, String)>) -> Mes {
         Mes { contents }
     }
+
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    /// Text of entry `index`.
+    pub fn get(&self, index: u32) -> Option<&str> {
+        self.contents.get(&index).map(|(_, text)| text.as_str())
+    }
+
+    /// Optional text between index and text field of entry `index`, `{100}middle{Hello}`.
+    pub fn middle(&self, index: u32) -> Option<&str> {
+        self.contents.get(&index)?.0.as_deref()
+    }
+
+    /// Indices and texts of all entries in ascending order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u32, &str)> {
+        self.range(..)
+    }
+
+    /// Indices and texts of the entries within `range` in ascending order, e.g. `mes.range(1000..2000)`.
+    pub fn range(
+        &self,
+        range: impl RangeBounds<u32>,
+    ) -> impl DoubleEndedIterator<Item = (u32, &str)> {
+        self.contents
+            .range(range)
+            .map(|(index, (_, text))| (*index, text.as_str()))
+    }
+
+    pub fn first(&self) -> Option<(u32, &str)> {
+        self.iter().next()
+    }
+
+    /// First entry with an index above `index`.
+    pub fn next(&self, index: u32) -> Option<(u32, &str)> {
+        self.range(index.checked_add(1)?..).next()
+    }
+}
+
+#[test]
+fn test_parse_entries() {
+    let mes = Mes::from_contents(
+        "// header comment\r\n\
+         {1}{Single line}\r\n\
+         { 2 } {spans\r\nlines} // trailing comment\n\
+         {3} middle {text with {braces} and // slashes}\n\
+         {4}{first}{second} stray {5}{same line}\n\
+         {1}{Duplicate}\n",
+    )
+    .unwrap();
+    assert_eq!(mes.len(), 5);
+    assert_eq!(mes.get(1), Some("Duplicate"));
+    assert_eq!(mes.get(2), Some("spans\nlines"));
+    assert_eq!(mes.middle(2), None);
+    assert_eq!(mes.middle(3), Some("middle"));
+    assert_eq!(mes.get(3), Some("text with {braces} and // slashes"));
+    assert_eq!(mes.get(4), Some("first"));
+    assert_eq!(mes.get(5), Some("same line"));
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |raw| match Mes::parse(raw, "test.mes") {
+        Err(MesError::Syntax { line, kind, .. }) => (line, kind),
+        other => panic!("{raw:?} should not parse: {other:?}"),
+    };
+    assert_eq!(
+        error("{1}{a}\n\n{2}{b"),
+        (3, MesSyntaxError::UnterminatedField)
+    );
+    assert_eq!(
+        error("{x}{a}"),
+        (1, MesSyntaxError::InvalidIndex("x".to_string()))
+    );
+    assert_eq!(error("{1}"), (1, MesSyntaxError::MissingText(1)));
+    assert_eq!(
+        error("{1}{a}\n{b}{c}"),
+        (2, MesSyntaxError::InvalidIndex("b".to_string()))
+    );
+    assert_eq!(
+        Mes::parse("{1}", "test.mes").unwrap_err().to_string(),
+        "test.mes:1: entry 1 has no text"
+    );
+}
+
+#[test]
+fn test_ordered_lookups() {
+    let mes = Mes::from_contents("{2000}{c}\n{1000}{a}\n{1500}{b}\n{10}{x}").unwrap();
+    let indices = |entries: Vec<(u32, &str)>| {
+        entries
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(indices(mes.iter().collect()), [10, 1000, 1500, 2000]);
+    assert_eq!(indices(mes.range(1000..2000).collect()), [1000, 1500]);
+    assert_eq!(mes.first(), Some((10, "x")));
+    assert_eq!(mes.next(1000), Some((1500, "b")));
+    assert_eq!(mes.next(2000), None);
+    assert_eq!(mes.range(..1000).next_back(), Some((10, "x")));
 }
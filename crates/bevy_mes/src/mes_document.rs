@@ -0,0 +1,445 @@
+use bevy::log::warn;
+use thiserror::Error;
+
+use crate::{MesEncoding, MesError, MesSyntaxError};
+
+/// A `.mes` file as written, for tools that edit and save them.
+/// Unlike [`crate::Mes`] it keeps the order of the entries, comments and everything in between,
+/// so a document that is parsed and written again comes out byte for byte as it was read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MesDocument {
+    pub items: Vec<MesItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MesItem {
+    Entry(MesEntry),
+    /// A comment outside of entries, without the leading `//` and the line break.
+    Comment(String),
+    /// Whitespace, line breaks and skipped input between the other items, written back as read.
+    Trivia(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MesEntry {
+    pub index: u32,
+    /// Optional text between the index and the text field, `{100}middle{Hello}`.
+    pub middle: Option<String>,
+    pub text: String,
+    /// The entry as read, written back as long as index, middle and text are unchanged.
+    source: Option<MesEntrySource>,
+}
+
+/// Fields of a parsed entry and the text they were read from, including whitespace and comments between them.
+#[derive(Clone, Debug, PartialEq)]
+struct MesEntrySource {
+    index: u32,
+    middle: Option<String>,
+    text: String,
+    raw: String,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MesWriteError {
+    #[error("entry {0} has unbalanced braces")]
+    UnbalancedBraces(u32),
+    #[error("text can not be encoded as {0:?}")]
+    Unencodable(MesEncoding),
+}
+
+impl MesEntry {
+    pub fn new(index: u32, text: impl Into<String>) -> MesEntry {
+        MesEntry {
+            index,
+            middle: None,
+            text: text.into(),
+            source: None,
+        }
+    }
+
+    /// The entry as read if it is unchanged, otherwise `{index}middle{text}` with Windows line endings.
+    fn write(&self) -> Result<String, MesWriteError> {
+        if let Some(source) = &self.source
+            && source.index == self.index
+            && source.middle == self.middle
+            && source.text == self.text
+        {
+            return Ok(source.raw.clone());
+        }
+        let middle = self.middle.as_deref().unwrap_or_default();
+        if !balanced(&self.text) || middle.contains(['{', '}']) {
+            return Err(MesWriteError::UnbalancedBraces(self.index));
+        }
+        Ok(format!(
+            "{{{}}}{}{{{}}}",
+            self.index,
+            crlf(middle),
+            crlf(&self.text)
+        ))
+    }
+}
+
+impl MesDocument {
+    /// Parses the contents of a `.mes` file, see [`crate::Mes::parse`] for the format.
+    pub fn parse(raw_content: &str, file: &str) -> Result<MesDocument, MesError> {
+        MesParser::new(raw_content, file)
+            .parse()
+            .map_err(|(line, kind)| MesError::Syntax {
+                file: file.to_string(),
+                line,
+                kind,
+            })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &MesEntry> {
+        self.items.iter().filter_map(|item| match item {
+            MesItem::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Returns the entry the game uses for `index`, the last one if it appears more than once.
+    pub fn get(&self, index: u32) -> Option<&MesEntry> {
+        self.entries().filter(|entry| entry.index == index).last()
+    }
+
+    pub fn get_mut(&mut self, index: u32) -> Option<&mut MesEntry> {
+        self.items
+            .iter_mut()
+            .filter_map(|item| match item {
+                MesItem::Entry(entry) if entry.index == index => Some(entry),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Replaces the text of entry `index`. A missing entry is inserted on its own line
+    /// in front of the first entry with a higher index.
+    pub fn set(&mut self, index: u32, text: impl Into<String>) {
+        if let Some(entry) = self.get_mut(index) {
+            entry.text = text.into();
+            return;
+        }
+        let entry = MesItem::Entry(MesEntry::new(index, text));
+        let line_break = || MesItem::Trivia("\r\n".to_string());
+        match self
+            .items
+            .iter()
+            .position(|item| matches!(item, MesItem::Entry(entry) if entry.index > index))
+        {
+            Some(position) => {
+                self.items.splice(position..position, [entry, line_break()]);
+            }
+            None => {
+                if !self.items.is_empty() && !self.ends_with_line_break(self.items.len()) {
+                    self.items.push(line_break());
+                }
+                self.items.extend([entry, line_break()]);
+            }
+        }
+    }
+
+    /// Removes every entry with `index` and returns the last one.
+    /// The line break after an entry is removed along with it if the entry started its line.
+    pub fn remove(&mut self, index: u32) -> Option<MesEntry> {
+        let mut removed = None;
+        let mut position = 0;
+        while position < self.items.len() {
+            let MesItem::Entry(entry) = &self.items[position] else {
+                position += 1;
+                continue;
+            };
+            if entry.index != index {
+                position += 1;
+                continue;
+            }
+            let starts_line = position == 0 || self.ends_with_line_break(position);
+            let MesItem::Entry(entry) = self.items.remove(position) else {
+                unreachable!();
+            };
+            removed = Some(entry);
+            if starts_line && let Some(MesItem::Trivia(trivia)) = self.items.get_mut(position) {
+                let rest = trivia
+                    .strip_prefix("\r\n")
+                    .or_else(|| trivia.strip_prefix('\n'))
+                    .map(str::to_string);
+                match rest {
+                    Some(rest) if rest.is_empty() => {
+                        self.items.remove(position);
+                    }
+                    Some(rest) => *trivia = rest,
+                    None => {}
+                }
+            }
+        }
+        removed
+    }
+
+    /// Whether the items before `position` end with a line break.
+    fn ends_with_line_break(&self, position: usize) -> bool {
+        matches!(&self.items[..position], [.., MesItem::Trivia(trivia)] if trivia.ends_with('\n'))
+    }
+
+    /// Writes the document, unchanged entries, comments and trivia as read and new or edited entries with
+    /// Windows line endings, the way the game's own files are stored.
+    /// Fails if a field contains unbalanced braces, the game would read past its end.
+    pub fn write(&self) -> Result<String, MesWriteError> {
+        let mut output = String::new();
+        for item in &self.items {
+            match item {
+                MesItem::Entry(entry) => output.push_str(&entry.write()?),
+                MesItem::Comment(comment) => output.push_str(&format!("//{comment}")),
+                MesItem::Trivia(trivia) => output.push_str(trivia),
+            }
+        }
+        Ok(output)
+    }
+
+    /// Writes the document and encodes it, [`MesEncoding::Auto`] picks Windows-1252 like the original release.
+    pub fn to_bytes(&self, encoding: MesEncoding) -> Result<Vec<u8>, MesWriteError> {
+        encoding
+            .encode(&self.write()?)
+            .ok_or(MesWriteError::Unencodable(encoding))
+    }
+}
+
+fn crlf(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\n', "\r\n")
+}
+
+fn balanced(field: &str) -> bool {
+    let mut depth = 0usize;
+    for char in field.chars() {
+        match char {
+            '{' => depth += 1,
+            '}' if depth == 0 => return false,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+type ParseResult<T> = Result<T, (usize, MesSyntaxError)>;
+
+struct MesParser<'a> {
+    source: &'a str,
+    position: usize,
+    file: &'a str,
+    line: usize,
+}
+
+impl<'a> MesParser<'a> {
+    fn new(source: &'a str, file: &'a str) -> MesParser<'a> {
+        MesParser {
+            source,
+            position: 0,
+            file,
+            line: 1,
+        }
+    }
+
+    fn parse(mut self) -> ParseResult<MesDocument> {
+        let mut document = MesDocument::default();
+        // whether only whitespace has been read since the last line break
+        let mut line_start = true;
+        loop {
+            let start = self.position;
+            let Some(next) = self.peek() else {
+                break;
+            };
+            match next {
+                '/' if self.at_comment() => {
+                    let comment = self.read_comment();
+                    document.items.push(MesItem::Comment(comment));
+                    line_start = false;
+                }
+                '{' => {
+                    let line = self.line;
+                    let raw_index = self.read_field()?;
+                    match raw_index.trim().parse() {
+                        Ok(index) => {
+                            let entry = self.read_entry(start, index, line)?;
+                            document.items.push(MesItem::Entry(entry));
+                        }
+                        // the game reads the first field after the index, later ones are ignored
+                        Err(_) if !line_start => {
+                            warn!("{}:{line}: skipping extra field {{{raw_index}}}", self.file);
+                            self.push_trivia(&mut document, start);
+                        }
+                        Err(_) => return Err((line, MesSyntaxError::InvalidIndex(raw_index))),
+                    }
+                    line_start = false;
+                }
+                _ if next.is_whitespace() => {
+                    self.bump();
+                    self.push_trivia(&mut document, start);
+                    line_start |= next == '\n';
+                }
+                _ => {
+                    let skipped = self.read_unbraced();
+                    warn!("{}:{}: skipping '{}'", self.file, self.line, skipped.trim());
+                    self.push_trivia(&mut document, start);
+                    line_start = false;
+                }
+            }
+        }
+        Ok(document)
+    }
+
+    /// Adds the input read since `start` to the trivia at the end of the document.
+    fn push_trivia(&self, document: &mut MesDocument, start: usize) {
+        let trivia = &self.source[start..self.position];
+        match document.items.last_mut() {
+            Some(MesItem::Trivia(last)) => last.push_str(trivia),
+            _ => document.items.push(MesItem::Trivia(trivia.to_string())),
+        }
+    }
+
+    /// Reads the rest of an entry starting at `start` whose index field has been read.
+    fn read_entry(&mut self, start: usize, index: u32, line: usize) -> ParseResult<MesEntry> {
+        let mut middle = String::new();
+        loop {
+            middle.push_str(&self.read_unbraced());
+            match self.peek() {
+                Some('\n') => {
+                    self.bump();
+                    middle.push('\n');
+                }
+                Some('/') if self.at_comment() => {
+                    self.read_comment();
+                }
+                Some('{') => break,
+                _ => return Err((line, MesSyntaxError::MissingText(index))),
+            }
+        }
+        let middle = middle.trim();
+        let middle = (!middle.is_empty()).then(|| middle.replace("\r\n", "\n"));
+        let text = self.read_field()?.replace("\r\n", "\n");
+        Ok(MesEntry {
+            index,
+            middle: middle.clone(),
+            text: text.clone(),
+            source: Some(MesEntrySource {
+                index,
+                middle,
+                text,
+                raw: self.source[start..self.position].to_string(),
+            }),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.position += char.len_utf8();
+        if char == '\n' {
+            self.line += 1;
+        }
+        Some(char)
+    }
+
+    fn at_comment(&self) -> bool {
+        self.source[self.position..].starts_with("//")
+    }
+
+    /// Reads a `//` comment up to the end of the line and returns it without the slashes and the line break.
+    fn read_comment(&mut self) -> String {
+        self.position += 2;
+        let start = self.position;
+        while !self.source[self.position..].starts_with(['\n'])
+            && !self.source[self.position..].starts_with("\r\n")
+            && self.bump().is_some()
+        {}
+        self.source[start..self.position].to_string()
+    }
+
+    /// Reads up to the next field, comment or line break.
+    fn read_unbraced(&mut self) -> String {
+        let start = self.position;
+        while !self.at_comment() && self.peek().is_some_and(|char| char != '{' && char != '\n') {
+            self.bump();
+        }
+        self.source[start..self.position].to_string()
+    }
+
+    /// Reads a field starting at its opening brace and returns everything up to the matching closing brace.
+    fn read_field(&mut self) -> ParseResult<String> {
+        let line = self.line;
+        self.bump();
+        let start = self.position;
+        let mut depth = 0;
+        while let Some(char) = self.bump() {
+            match char {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    return Ok(self.source[start..self.position - 1].to_string());
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        Err((line, MesSyntaxError::UnterminatedField))
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let raw = "// Main menu\r\n\
+               \r\n\
+               {10}{New Game} // hotkey below\r\n\
+               {11}n{Load Game}\r\n\
+               {20}{spans\r\nlines}\r\n";
+    let mut document = MesDocument::parse(raw, "test.mes").unwrap();
+    assert_eq!(
+        document.items[..2],
+        [
+            MesItem::Comment(" Main menu".to_string()),
+            MesItem::Trivia("\r\n\r\n".to_string())
+        ]
+    );
+    assert_eq!(document.get(20).unwrap().text, "spans\nlines");
+    assert_eq!(document.write().unwrap(), raw);
+
+    document.set(11, "Spiel laden");
+    document.set(15, "Optionen");
+    document.remove(20);
+    assert_eq!(
+        document.write().unwrap(),
+        "// Main menu\r\n\r\n{10}{New Game} // hotkey below\r\n{11}n{Spiel laden}\r\n{15}{Optionen}\r\n"
+    );
+    assert_eq!(
+        document.to_bytes(MesEncoding::Auto).unwrap()[..12],
+        *b"// Main menu"
+    );
+
+    document.set(15, "broken }");
+    assert_eq!(document.write(), Err(MesWriteError::UnbalancedBraces(15)));
+}
+
+#[test]
+fn test_round_trip_keeps_layout() {
+    let raw = "\t{ 1 }  // between fields\n {a\nb} {2}{c}{ignored} stray\n//last\r\n{3}x{d}";
+    let mut document = MesDocument::parse(raw, "test.mes").unwrap();
+    assert_eq!(document.write().unwrap(), raw);
+    assert_eq!(document.entries().count(), 3);
+    assert_eq!(document.get(3).unwrap().middle.as_deref(), Some("x"));
+
+    document.set(4, "new");
+    assert!(
+        document
+            .write()
+            .unwrap()
+            .ends_with("{3}x{d}\r\n{4}{new}\r\n")
+    );
+    document.get_mut(1).unwrap().text = "edited".to_string();
+    assert!(
+        document
+            .write()
+            .unwrap()
+            .starts_with("\t{1}{edited} {2}{c}")
+    );
+}
@@ -0,0 +1,84 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+/// Codepage a `.mes` file is stored in. The original releases use the Windows codepage of their language.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MesEncoding {
+    /// Honours a byte order mark, otherwise uses UTF-8 if the file is valid UTF-8 and Windows-1252 if not.
+    #[default]
+    Auto,
+    Utf8,
+    /// Central European releases, e.g. Polish.
+    Windows1250,
+    /// Russian releases.
+    Windows1251,
+    /// English, German, French and other Western European releases. Also covers Latin-1.
+    Windows1252,
+}
+
+impl MesEncoding {
+    /// Decodes `bytes` into a string. Returns `None` if they aren't valid in the selected encoding.
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        let encoding = match self {
+            MesEncoding::Auto => Self::detect(bytes),
+            MesEncoding::Utf8 => UTF_8,
+            MesEncoding::Windows1250 => WINDOWS_1250,
+            MesEncoding::Windows1251 => WINDOWS_1251,
+            MesEncoding::Windows1252 => WINDOWS_1252,
+        };
+        let (contents, _, had_errors) = encoding.decode(bytes);
+        match had_errors {
+            true => None,
+            false => Some(contents.into_owned()),
+        }
+    }
+
+    /// Encodes `text` for writing. [`MesEncoding::Auto`] uses Windows-1252, which every release can read.
+    /// Returns `None` if `text` contains characters the encoding can't represent.
+    pub fn encode(self, text: &str) -> Option<Vec<u8>> {
+        let encoding = match self {
+            MesEncoding::Auto | MesEncoding::Windows1252 => WINDOWS_1252,
+            MesEncoding::Utf8 => UTF_8,
+            MesEncoding::Windows1250 => WINDOWS_1250,
+            MesEncoding::Windows1251 => WINDOWS_1251,
+        };
+        let (bytes, _, had_errors) = encoding.encode(text);
+        match had_errors {
+            true => None,
+            false => Some(bytes.into_owned()),
+        }
+    }
+
+    fn detect(bytes: &[u8]) -> &'static Encoding {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return encoding;
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(_) => UTF_8,
+            Err(_) => WINDOWS_1252,
+        }
+    }
+}
+
+#[test]
+fn test_decode() {
+    let german = b"{1}{Gr\xfc\xdfe}";
+    assert_eq!(MesEncoding::Auto.decode(german).unwrap(), "{1}{Grüße}");
+    assert_eq!(MesEncoding::Utf8.decode(german), None);
+    assert_eq!(
+        MesEncoding::Auto.decode("{1}{Grüße}".as_bytes()).unwrap(),
+        "{1}{Grüße}"
+    );
+    assert_eq!(
+        MesEncoding::Windows1250
+            .decode(b"{1}{\xb3\xf3d\x9f}")
+            .unwrap(),
+        "{1}{łódź}"
+    );
+    assert_eq!(
+        MesEncoding::Windows1252
+            .decode(b"{1}{\x80 caf\xe9}")
+            .unwrap(),
+        "{1}{€ café}"
+    );
+}
@@ -1,36 +1,49 @@
-use bevy::{
-    asset::{AssetLoader, AsyncReadExt},
-    prelude::*,
-};
+use bevy::{asset::AssetLoader, prelude::*};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{Mes, mes::MesError};
+use crate::{Mes, MesEncoding, mes::MesError};
 
 #[derive(Default)]
 pub struct MesLoader;
 
+/// Settings for [`MesLoader`], usable with `load_with_settings` or in a `.meta` file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MesLoaderSettings {
+    pub encoding: MesEncoding,
+}
+
 #[derive(Debug, Error)]
 pub enum MesLoaderError {
     #[error("Could not read file")]
     Io(#[from] std::io::Error),
     #[error("Could not decode file")]
     Format(#[from] MesError),
+    #[error("File is not valid {0:?}")]
+    Encoding(MesEncoding),
 }
 
 impl AssetLoader for MesLoader {
     type Asset = Mes;
-    type Settings = ();
+    type Settings = MesLoaderSettings;
     type Error = MesLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
-        _settings: &Self::Settings,
-        _load_context: &mut bevy::asset::LoadContext<'_>,
+        settings: &Self::Settings,
+        load_context: &mut bevy::asset::LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
-        let mut contents = String::new();
-        reader.read_to_string(&mut contents).await?;
-        Ok(Mes::from_contents(&contents)?)
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let contents = settings
+            .encoding
+            .decode(&bytes)
+            .ok_or(MesLoaderError::Encoding(settings.encoding))?;
+        Ok(Mes::parse(
+            &contents,
+            &load_context.path().display().to_string(),
+        )?)
     }
 
     fn extensions(&self) -> &[&str] {
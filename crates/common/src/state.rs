@@ -7,6 +7,8 @@ pub enum AppState {
     Video(VideoState),
     Loading,
     MainMenu,
+    /// Loading game data failed, shows what went wrong.
+    Error,
 }
 
 impl AppState {
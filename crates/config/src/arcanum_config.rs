@@ -0,0 +1,182 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+/// Name of the original game's settings file inside the data directory.
+pub const ARCANUM_CFG: &str = "arcanum.cfg";
+
+/// Settings of the original game, `key=value` lines in `arcanum.cfg`.
+/// Screen, sound and gameplay options are all stored here, keys this crate doesn't know
+/// as well as comments and other lines are kept as they are.
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct ArcanumConfig {
+    path: PathBuf,
+    lines: Vec<ConfigLine>,
+}
+
+/// A line of a config file as read, written back unchanged unless its option is set.
+#[derive(Clone, Debug, PartialEq)]
+struct ConfigLine {
+    raw: String,
+    /// Trimmed key and value of a `key=value` line.
+    option: Option<(String, String)>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Could not access file")]
+    Io(#[from] std::io::Error),
+}
+
+impl ArcanumConfig {
+    /// Values [`ArcanumConfig::get`] falls back to for options missing from `arcanum.cfg`.
+    /// The key names are not verified against a file written by the game yet,
+    /// so they are never written to the file unless they are [`ArcanumConfig::set`].
+    const DEFAULTS: [(&str, &str); 14] = [
+        ("difficulty", "1"),
+        ("violence filter", "0"),
+        ("combat taunts", "1"),
+        ("always run", "0"),
+        ("auto attack", "0"),
+        ("follower skills", "1"),
+        ("turn-based", "0"),
+        ("fast turn-based", "0"),
+        ("auto save", "1"),
+        ("text duration", "2"),
+        ("brightness", "0"),
+        ("effects volume", "5"),
+        ("voice volume", "5"),
+        ("music volume", "5"),
+    ];
+
+    /// Configuration without any options set, stored at `path` when saved.
+    pub fn new(path: impl Into<PathBuf>) -> ArcanumConfig {
+        ArcanumConfig::parse(path, "")
+    }
+
+    /// Reads `arcanum.cfg` from the data directory. A missing file is only created once the config is saved.
+    pub fn load(data_dir: &Path) -> Result<ArcanumConfig, ConfigError> {
+        let path = data_dir.join(ARCANUM_CFG);
+        if !path.exists() {
+            info!("no {}, using the defaults", path.display());
+            return Ok(ArcanumConfig::new(path));
+        }
+        let contents = fs::read_to_string(&path)?;
+        Ok(ArcanumConfig::parse(path, &contents))
+    }
+
+    /// Parses the contents of a config file.
+    pub fn parse(path: impl Into<PathBuf>, contents: &str) -> ArcanumConfig {
+        ArcanumConfig {
+            path: path.into(),
+            lines: parse_lines(contents),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        Ok(fs::write(&self.path, self.write())?)
+    }
+
+    /// Writes the lines in the order they were read, with Windows line endings.
+    /// Options that were set are written as `key=value`, every other line as it was read.
+    /// Defaults of options that were never set are left out.
+    pub fn write(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| format!("{}\r\n", line.raw))
+            .collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Value of an option, its default if the file doesn't have it.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.option.as_ref())
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+            .or_else(|| {
+                ArcanumConfig::DEFAULTS
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, value)| *value)
+            })
+    }
+
+    /// Numeric option, `None` if it is missing or not a number.
+    pub fn get_int(&self, key: &str) -> Option<i32> {
+        self.get(key)?.parse().ok()
+    }
+
+    /// Option the game stores as `0` or `1`.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_int(key).map(|value| value != 0)
+    }
+
+    /// Replaces an option or appends it if it is new.
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        let line = ConfigLine {
+            raw: format!("{key}={}", value.to_string()),
+            option: Some((key.to_string(), value.to_string())),
+        };
+        match self
+            .lines
+            .iter_mut()
+            .find(|line| line.option.as_ref().is_some_and(|(k, _)| k == key))
+        {
+            Some(old) => *old = line,
+            None => self.lines.push(line),
+        }
+    }
+}
+
+/// Splits a config file into lines, `key=value` lines are options.
+fn parse_lines(contents: &str) -> Vec<ConfigLine> {
+    contents
+        .lines()
+        .map(|line| ConfigLine {
+            raw: line.to_string(),
+            option: line
+                .split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string())),
+        })
+        .collect()
+}
+
+/// Reads `key=value` lines, lines without `=` are skipped.
+pub(crate) fn parse_entries(contents: &str) -> Vec<(String, String)> {
+    parse_lines(contents)
+        .into_iter()
+        .filter_map(|line| line.option)
+        .collect()
+}
+
+#[test]
+fn test_parse_and_write() {
+    let mut config = ArcanumConfig::parse(
+        "arcanum.cfg",
+        "// sound\r\nmusic volume=2\r\nunknown option = abc\r\n\r\n",
+    );
+    assert_eq!(config.get_int("music volume"), Some(2));
+    assert_eq!(config.get("unknown option"), Some("abc"));
+    assert_eq!(config.get_bool("follower skills"), Some(true));
+    assert_eq!(config.get_int("unknown option"), None);
+
+    assert_eq!(config.get_int("difficulty"), Some(1));
+    assert!(!config.write().contains("difficulty"));
+
+    config.set("difficulty", 2);
+    let written = config.write();
+    assert_eq!(
+        written,
+        "// sound\r\nmusic volume=2\r\nunknown option = abc\r\n\r\ndifficulty=2\r\n"
+    );
+    assert_eq!(ArcanumConfig::parse("arcanum.cfg", &written), config);
+}
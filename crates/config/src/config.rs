@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+
+use crate::{ARCANUM_CFG, ArcanumConfig, DataDir};
+
+/// Loads [`ArcanumConfig`] at startup and saves it whenever it changes.
+/// [`DataDir`] has to be inserted before `DefaultPlugins`, since it configures the asset source.
+pub fn plugin(app: &mut App) {
+    app.add_systems(PreStartup, load_config).add_systems(
+        Last,
+        save_config
+            .run_if(resource_changed::<ArcanumConfig>.and(not(resource_added::<ArcanumConfig>))),
+    );
+}
+
+fn load_config(data_dir: Res<DataDir>, mut commands: Commands) {
+    let config = ArcanumConfig::load(&data_dir.path).unwrap_or_else(|err| {
+        warn!("using the default settings, could not read {ARCANUM_CFG}: {err}");
+        ArcanumConfig::new(data_dir.path.join(ARCANUM_CFG))
+    });
+    commands.insert_resource(config);
+}
+
+fn save_config(config: Res<ArcanumConfig>) {
+    if let Err(err) = config.save() {
+        error!("could not save {}: {err}", config.path().display());
+    }
+}
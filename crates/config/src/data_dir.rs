@@ -0,0 +1,128 @@
+use std::{
+    ffi::OsString,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+
+use crate::arcanum_config::parse_entries;
+
+/// Command line flag pointing to the Arcanum installation, `--data-dir <path>` or `--data-dir=<path>`.
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+/// Environment variable pointing to the Arcanum installation.
+pub const DATA_DIR_ENV: &str = "RUSTCARNUM_DATA_DIR";
+/// Config file in the working directory, pointing to the installation with a `data dir=<path>` line.
+pub const DATA_DIR_CONFIG: &str = "rustcarnum.cfg";
+const DATA_DIR_KEY: &str = "data dir";
+
+/// Where the [`DataDir`] was configured.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataDirSource {
+    Flag,
+    Env,
+    ConfigFile(PathBuf),
+    /// Bevy's `assets` directory.
+    Default,
+}
+
+impl fmt::Display for DataDirSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataDirSource::Flag => write!(f, "the {DATA_DIR_FLAG} flag"),
+            DataDirSource::Env => write!(f, "{DATA_DIR_ENV}"),
+            DataDirSource::ConfigFile(path) => write!(f, "{}", path.display()),
+            DataDirSource::Default => write!(f, "the default"),
+        }
+    }
+}
+
+/// Directory of the Arcanum installation the dats and `arcanum.cfg` are read from.
+#[derive(Clone, Debug, Eq, PartialEq, Resource)]
+pub struct DataDir {
+    pub path: PathBuf,
+    pub source: DataDirSource,
+}
+
+impl DataDir {
+    /// Finds the installation, checking the command line flag, the environment variable,
+    /// `rustcarnum.cfg` in the working directory and finally falling back to Bevy's `assets` directory.
+    pub fn resolve() -> DataDir {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let config_file = cwd.join(DATA_DIR_CONFIG);
+        let config = fs::read_to_string(&config_file).ok().and_then(|contents| {
+            parse_entries(&contents)
+                .into_iter()
+                .find(|(key, _)| key == DATA_DIR_KEY)
+                .map(|(_, value)| (config_file, value))
+        });
+        DataDir::resolve_from(
+            std::env::args().skip(1),
+            std::env::var_os(DATA_DIR_ENV),
+            config,
+            &cwd,
+        )
+    }
+
+    fn resolve_from(
+        mut args: impl Iterator<Item = String>,
+        env: Option<OsString>,
+        config: Option<(PathBuf, String)>,
+        cwd: &Path,
+    ) -> DataDir {
+        let mut flag = None;
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix(DATA_DIR_FLAG) {
+                Some("") => flag = args.next(),
+                Some(value) if value.starts_with('=') => flag = Some(value[1..].to_string()),
+                _ => continue,
+            }
+            break;
+        }
+        let (path, source) = if let Some(path) = flag {
+            (cwd.join(path), DataDirSource::Flag)
+        } else if let Some(path) = env {
+            (cwd.join(path), DataDirSource::Env)
+        } else if let Some((file, path)) = config {
+            let dir = file.parent().unwrap_or(cwd).join(path);
+            (dir, DataDirSource::ConfigFile(file))
+        } else {
+            (
+                FileAssetReader::get_base_path().join("assets"),
+                DataDirSource::Default,
+            )
+        };
+        DataDir { path, source }
+    }
+}
+
+#[test]
+fn test_resolve_order() {
+    let cwd = Path::new("/home/arcanum");
+    let args = |args: &[&str]| {
+        args.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .into_iter()
+    };
+    let config = Some((cwd.join(DATA_DIR_CONFIG), "../games/Arcanum".to_string()));
+
+    let dir = DataDir::resolve_from(
+        args(&["--data-dir", "install"]),
+        Some("env".into()),
+        config.clone(),
+        cwd,
+    );
+    assert_eq!(dir.path, cwd.join("install"));
+    assert_eq!(dir.source, DataDirSource::Flag);
+    let dir = DataDir::resolve_from(args(&["--data-dir=/opt/arcanum"]), None, None, cwd);
+    assert_eq!(dir.path, Path::new("/opt/arcanum"));
+
+    let dir = DataDir::resolve_from(args(&[]), Some("/mnt/arcanum".into()), config.clone(), cwd);
+    assert_eq!(dir.path, Path::new("/mnt/arcanum"));
+    assert_eq!(dir.source, DataDirSource::Env);
+
+    let dir = DataDir::resolve_from(args(&["--other"]), None, config, cwd);
+    assert_eq!(dir.path, cwd.join("../games/Arcanum"));
+    assert!(matches!(dir.source, DataDirSource::ConfigFile(_)));
+}
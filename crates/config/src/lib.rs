@@ -0,0 +1,7 @@
+mod arcanum_config;
+mod config;
+mod data_dir;
+
+pub use arcanum_config::{ARCANUM_CFG, ArcanumConfig, ConfigError};
+pub use config::plugin;
+pub use data_dir::{DATA_DIR_CONFIG, DATA_DIR_ENV, DATA_DIR_FLAG, DataDir, DataDirSource};
@@ -0,0 +1,31 @@
+/// Whether the game can start without a dat.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DatRequirement {
+    Required,
+    Optional,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DatFile {
+    /// Path relative to the data directory, see `config::DataDir`.
+    pub path: &'static str,
+    pub requirement: DatRequirement,
+}
+
+impl DatFile {
+    const fn new(path: &'static str, requirement: DatRequirement) -> DatFile {
+        DatFile { path, requirement }
+    }
+}
+
+/// The dats of the base game and the default module in load order, later ones override files of earlier ones.
+/// The original game refuses to start or crashes without the required ones, see the README.
+/// Nothing is known about installs without the module dat, so without it only the base game's files are used.
+pub const DAT_FILES: [DatFile; 6] = [
+    DatFile::new("tig.dat", DatRequirement::Required),
+    DatFile::new("arcanum1.dat", DatRequirement::Required),
+    DatFile::new("arcanum2.dat", DatRequirement::Required),
+    DatFile::new("arcanum3.dat", DatRequirement::Required),
+    DatFile::new("Arcanum4.dat", DatRequirement::Optional),
+    DatFile::new("modules/Arcanum.dat", DatRequirement::Optional),
+];
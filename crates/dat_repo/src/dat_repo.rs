@@ -1,25 +1,32 @@
 use std::path::Path;
 
 use bevy::{asset::io::memory::Dir, platform::collections::HashMap, prelude::*};
+use bevy_art::{ArtIdError, ArtLoaderSettings, ArtType, TigArtId, TileArtId};
 use bevy_dat::Dat;
 use bevy_mes::Mes;
+use thiserror::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum DatRepoLoadingError {
+    #[error("{0} is in none of the dats")]
     FileNotFound(String),
+    #[error("no file in the dats matches the pattern")]
     PatternMatch,
+    #[error("{0:?} is not loaded")]
     ArtTypeNotFound(MesFileType),
+    #[error("there is no entry {0}")]
     EntryNotFound(u32),
-    InvalidArtType,
+    #[error("{0:?} art has no name file")]
+    UnsupportedArtType(ArtType),
+    #[error("invalid art id")]
+    InvalidArtId(#[from] ArtIdError),
 }
 
 #[derive(Default, Resource)]
 pub struct DatRepo {
     pub dir: Dir,
-    dats: Vec<Dat>,
+    dats: Vec<(DatLayer, Dat)>,
     dat_handles: Vec<Handle<Dat>>,
-    pub mes_handles: Vec<(MesFileType, Handle<Mes>)>,
-    mes_filenames: Vec<(MesFileType, String)>,
     mes: HashMap<MesFileType, Mes>,
 }
 
@@ -28,16 +35,17 @@ impl DatRepo {
         self.dat_handles.push(handle);
     }
 
-    pub fn loaded_dat(&self, asset_server: &AssetServer) -> bool {
-        self.dat_handles
-            .iter()
-            .all(|handle| asset_server.is_loaded(handle))
-    }
-
     pub fn fill(&mut self, dats: &Assets<Dat>) {
         for handle in &self.dat_handles {
-            let dat = dats.get(handle).unwrap();
-            self.dats.push(dat.clone());
+            // optional dats that failed to load are skipped
+            let Some(dat) = dats.get(handle) else {
+                continue;
+            };
+            let layer = match handle.path() {
+                Some(path) if path.path().starts_with("modules") => DatLayer::Module,
+                _ => DatLayer::Base,
+            };
+            self.dats.push((layer, dat.clone()));
         }
         self.dat_handles.clear();
     }
@@ -45,7 +53,7 @@ impl DatRepo {
     /// Loads a file from the .dat file table entry and adds it to the
     /// memory storage.
     pub fn load_file_directly(&self, pattern: &str) -> Result<String, DatRepoLoadingError> {
-        for dat in self.dats.iter().rev() {
+        for (_, dat) in self.dats.iter().rev() {
             if let Some(entry) = dat.get(pattern) {
                 self.dir
                     .insert_asset(Path::new(&entry.filename), dat.bytes(entry));
@@ -55,6 +63,23 @@ impl DatRepo {
         Err(DatRepoLoadingError::FileNotFound(pattern.to_string()))
     }
 
+    /// Like [`DatRepo::load_file_directly`], but only looks at the dats of one layer.
+    /// The file is stored below the layer's directory, so both versions of a file can be loaded at once.
+    pub fn load_file_from(
+        &self,
+        pattern: &str,
+        layer: DatLayer,
+    ) -> Result<String, DatRepoLoadingError> {
+        for (_, dat) in self.dats.iter().rev().filter(|(l, _)| *l == layer) {
+            if let Some(entry) = dat.get(pattern) {
+                let path = format!("{}/{}", layer.dir(), entry.filename);
+                self.dir.insert_asset(Path::new(&path), dat.bytes(entry));
+                return Ok(format!("memory://{path}"));
+            }
+        }
+        Err(DatRepoLoadingError::FileNotFound(pattern.to_string()))
+    }
+
     pub fn load_file_match(
         &self,
         dats: &Assets<Dat>,
@@ -73,89 +98,173 @@ impl DatRepo {
         Err(DatRepoLoadingError::PatternMatch)
     }
 
-    pub fn add_mes_file_to_load(&mut self, key: MesFileType, filename: &str) {
-        self.mes_filenames.push((key, filename.to_string()));
+    /// Keeps a name list or rules file, string tables belong to `Strings` instead.
+    pub fn insert_mes(&mut self, key: MesFileType, mes: &Mes) {
+        self.mes.insert(key, mes.clone());
     }
 
-    pub fn load_next(&mut self, asset_server: &AssetServer) -> bool {
-        if let Some((key, filename)) = self.mes_filenames.pop() {
-            match self.load_file_directly(&filename) {
-                Ok(path) => {
-                    self.mes_handles.push((key, asset_server.load(path)));
-                }
-                Err(err) => warn!("{:?}", err),
-            }
-            return true;
-        }
-        false
+    /// Loads art `num` of a category, looking up its file name in the category's name `.mes`.
+    pub fn load_art(&self, name: Name, num: u32) -> Result<String, DatRepoLoadingError> {
+        let mes_type = MesFileType::Name(name);
+        let Some(mes) = self.mes.get(&mes_type) else {
+            return Err(DatRepoLoadingError::ArtTypeNotFound(mes_type));
+        };
+        let Some(file_name) = mes.get(num) else {
+            return Err(DatRepoLoadingError::EntryNotFound(num));
+        };
+        self.load_file_directly(&name.art_file(file_name))
     }
 
-    pub fn loaded_all(&self, asset_server: &AssetServer) -> bool {
-        self.mes_handles
-            .iter()
-            .all(|(_, handle)| asset_server.is_loaded(handle))
+    /// Loads interface art `num`, the number TIG creates its [`TigArtId`] from.
+    pub fn load_interface_art(&self, num: u32) -> Result<String, DatRepoLoadingError> {
+        let (path, _) = self.load_art_id(TigArtId::new(ArtType::Interface, num)?)?;
+        Ok(path)
     }
 
-    pub fn insert_mes(&mut self, key: MesFileType, mes: &Mes) {
-        self.mes.insert(key, mes.clone());
+    /// Loads the art file an art id from game data refers to, along with the settings to load it with,
+    /// see [`art_loader_settings`]. Frame, rotation and palette select parts of the file and are up to the caller.
+    pub fn load_art_id(
+        &self,
+        art_id: TigArtId,
+    ) -> Result<(String, ArtLoaderSettings), DatRepoLoadingError> {
+        let path = if let Some(tile) = art_id.tile() {
+            self.load_file_directly(&self.tile_art_file(tile)?)?
+        } else if let Some(wall) = art_id.wall() {
+            self.load_art(Name::Wall, wall.num)?
+        } else {
+            let art_type = art_id.art_type();
+            let name = Name::from_art_type(art_type)
+                .ok_or(DatRepoLoadingError::UnsupportedArtType(art_type))?;
+            self.load_art(name, art_id.num())?
+        };
+        Ok((path, art_loader_settings(art_id)))
     }
 
-    pub fn load_file_by_num(&self, num: u32) -> Result<String, DatRepoLoadingError> {
-        let art_type = match num {
-            _ => MesFileType::Name(Name::Interface),
-        };
-        let Some(mes) = self.mes.get(&art_type) else {
-            return Err(DatRepoLoadingError::ArtTypeNotFound(art_type));
+    /// Tiles are named after the terrains they are made of, see [`tile_file`].
+    fn tile_art_file(&self, tile: TileArtId) -> Result<String, DatRepoLoadingError> {
+        let mes_type = MesFileType::Name(Name::Tile);
+        let mes = self
+            .mes
+            .get(&mes_type)
+            .ok_or(DatRepoLoadingError::ArtTypeNotFound(mes_type))?;
+        let terrain = |num| {
+            mes.get(num)
+                .and_then(|entry| entry.split_whitespace().next())
+                .ok_or(DatRepoLoadingError::EntryNotFound(num))
         };
-        let Some((_, file_name)) = mes.contents.get(&num) else {
-            return Err(DatRepoLoadingError::EntryNotFound(num));
-        };
-        let path: &str = art_type.try_into()?;
-        let file = format!("{}{}", path, file_name);
-        self.load_file_directly(&file)
+        Ok(tile_file(
+            terrain(tile.num1)?,
+            terrain(tile.num2)?,
+            tile.variation,
+        ))
     }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+/// Path of the art of a tile made of terrain `name1` blending into `name2`, e.g. `art\tile\grsdrt0.art`.
+/// Tiles of a single terrain use `bse` for the second name, e.g. `art\tile\grsbse0.art`.
+fn tile_file(name1: &str, name2: &str, variation: u32) -> String {
+    let name2 = match name1 == name2 {
+        true => "bse",
+        false => name2,
+    };
+    format!("{}{name1}{name2}{variation}.art", Name::Tile.art_dir())
+}
+
+/// Where a dat comes from. Module dats are added after the base game dats and override their files.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DatLayer {
+    Module,
+    Base,
+}
+
+impl DatLayer {
+    fn dir(self) -> &'static str {
+        match self {
+            DatLayer::Module => "module",
+            DatLayer::Base => "base",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum MesFileType {
     Description,
     ItemEffect,
+    /// Labels and hotkeys of the main menu and its submenus.
+    MainMenu,
     Critter(MesCritterType),
     Name(Name),
     Portrait(Portrait),
 }
 
-impl TryInto<&str> for MesFileType {
-    type Error = DatRepoLoadingError;
-    fn try_into(self) -> std::result::Result<&'static str, Self::Error> {
-        let path = match self {
-            MesFileType::Name(Name::Scenery) => "art\\scenery\\",
-            MesFileType::Name(Name::Interface) => "art\\interface\\",
-            _ => return Err(DatRepoLoadingError::InvalidArtType),
-        };
-        Ok(path)
+impl MesFileType {
+    pub const ALL: [MesFileType; 19] = [
+        MesFileType::Description,
+        MesFileType::ItemEffect,
+        MesFileType::MainMenu,
+        MesFileType::Critter(MesCritterType::Xp),
+        MesFileType::Critter(MesCritterType::Base),
+        MesFileType::Name(Name::Scenery),
+        MesFileType::Name(Name::Interface),
+        MesFileType::Name(Name::UniqueNpc),
+        MesFileType::Name(Name::Monster),
+        MesFileType::Name(Name::EyeCandy),
+        MesFileType::Name(Name::Container),
+        MesFileType::Name(Name::Light),
+        MesFileType::Name(Name::Tile),
+        MesFileType::Name(Name::Roof),
+        MesFileType::Name(Name::Wall),
+        MesFileType::Name(Name::WallProto),
+        MesFileType::Name(Name::Structure),
+        MesFileType::Portrait(Portrait::Game),
+        MesFileType::Portrait(Portrait::User),
+    ];
+
+    /// Whether the file holds text shown to the player, which is looked up through the `Strings`
+    /// of the localization crate. The other files list art names or rules and are kept by [`DatRepo`].
+    pub fn is_strings(self) -> bool {
+        matches!(
+            self,
+            MesFileType::Description
+                | MesFileType::ItemEffect
+                | MesFileType::MainMenu
+                | MesFileType::Critter(MesCritterType::Base)
+        )
+    }
+
+    /// Path of the file inside the dats.
+    pub fn path(self) -> &'static str {
+        match self {
+            MesFileType::Description => "mes\\description.mes",
+            MesFileType::ItemEffect => "mes\\item_effect.mes",
+            MesFileType::MainMenu => "mes\\MainMenu.mes",
+            MesFileType::Critter(MesCritterType::Xp) => "rules\\xp_critter.mes",
+            MesFileType::Critter(MesCritterType::Base) => "mes\\critter.mes",
+            MesFileType::Name(Name::Scenery) => "art\\scenery\\scenery.mes",
+            MesFileType::Name(Name::Interface) => "art\\interface\\interface.mes",
+            MesFileType::Name(Name::UniqueNpc) => "art\\unique_npc\\unique_npc.mes",
+            MesFileType::Name(Name::Monster) => "art\\monster\\monster.mes",
+            MesFileType::Name(Name::EyeCandy) => "art\\eye_candy\\eye_candy.mes",
+            MesFileType::Name(Name::Container) => "art\\container\\container.mes",
+            MesFileType::Name(Name::Light) => "art\\light\\light.mes",
+            MesFileType::Name(Name::Tile) => "art\\tile\\tilename.mes",
+            MesFileType::Name(Name::Roof) => "art\\roof\\roofname.mes",
+            MesFileType::Name(Name::Wall) => "art\\wall\\wallname.mes",
+            MesFileType::Name(Name::WallProto) => "art\\wall\\wallproto.mes",
+            MesFileType::Name(Name::Structure) => "art\\structure\\structure.mes",
+            MesFileType::Portrait(Portrait::Game) => "portrait\\gameport.mes",
+            MesFileType::Portrait(Portrait::User) => "portrait\\userport.mes",
+        }
     }
-    // fn try_into(value: &MesFileType) -> std::result::Result<Self, Self::Error> {
-
-    //     let mes_type = match value {
-    //         "art\\scenery\\" => MesFileType::Name(Name::Scenery),
-    //         "art\\interface\\" => MesFileType::Name(Name::Interface),
-    //         "art\\unique_npc\\" => MesFileType::Name(Name::UniqueNpc),
-    //         "art\\monster\\" => MesFileType::Name(Name::Monster),
-    //         "art\\eye_candy\\" => MesFileType::Name(Name::EyeCandy),
-    //         _ => return Err(DatRepoLoadingError::InvalidArtType)
-    //     };
-    //     Ok(mes_type)
-    // }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum MesCritterType {
     Base,
     Xp,
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Name {
     Scenery,
     Interface,
@@ -167,12 +276,101 @@ pub enum Name {
     Tile,
     Roof,
     Wall,
+    /// Not referenced by art ids, loaded with [`DatRepo::load_art`] by entry number.
     WallProto,
+    /// Not referenced by art ids, loaded with [`DatRepo::load_art`] by entry number.
     Structure,
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+impl Name {
+    /// Directory of the art files listed in the category's name `.mes`.
+    pub fn art_dir(self) -> &'static str {
+        match self {
+            Name::Scenery => "art\\scenery\\",
+            Name::Interface => "art\\interface\\",
+            Name::UniqueNpc => "art\\unique_npc\\",
+            Name::Monster => "art\\monster\\",
+            Name::EyeCandy => "art\\eye_candy\\",
+            Name::Container => "art\\container\\",
+            Name::Light => "art\\light\\",
+            Name::Tile => "art\\tile\\",
+            Name::Roof => "art\\roof\\",
+            Name::Wall | Name::WallProto => "art\\wall\\",
+            Name::Structure => "art\\structure\\",
+        }
+    }
+
+    /// Category whose name `.mes` lists the files of an art type. Critters, items, portals,
+    /// misc art and facades build their file names differently and have none.
+    pub fn from_art_type(art_type: ArtType) -> Option<Name> {
+        let name = match art_type {
+            ArtType::Scenery => Name::Scenery,
+            ArtType::Interface => Name::Interface,
+            ArtType::UniqueNpc => Name::UniqueNpc,
+            ArtType::Monster => Name::Monster,
+            ArtType::EyeCandy => Name::EyeCandy,
+            ArtType::Container => Name::Container,
+            ArtType::Light => Name::Light,
+            ArtType::Tile => Name::Tile,
+            ArtType::Roof => Name::Roof,
+            ArtType::Wall => Name::Wall,
+            ArtType::Critter
+            | ArtType::Portal
+            | ArtType::Item
+            | ArtType::Misc
+            | ArtType::Facade => return None,
+        };
+        Some(name)
+    }
+
+    /// Path of the file a name `.mes` entry refers to. Entries hold the file name, optionally followed
+    /// by further values; tile, roof and wall names leave out the extension.
+    pub fn art_file(self, entry: &str) -> String {
+        let file_name = entry.split_whitespace().next().unwrap_or_default();
+        match file_name.contains('.') {
+            true => format!("{}{file_name}", self.art_dir()),
+            false => format!("{}{file_name}.art", self.art_dir()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Portrait {
     Game,
     User,
 }
+
+/// Loader settings an art id needs to render right, the [`ArtType::blend_mode`] of its type
+/// so lights and eye candy don't show black boxes.
+pub fn art_loader_settings(art_id: TigArtId) -> ArtLoaderSettings {
+    ArtLoaderSettings {
+        blend_mode: art_id.art_type().blend_mode(),
+        ..default()
+    }
+}
+
+#[test]
+fn test_art_files() {
+    let art_id = TigArtId::new(ArtType::EyeCandy, 12).unwrap();
+    let name = Name::from_art_type(art_id.art_type()).unwrap();
+    assert_eq!(name, Name::EyeCandy);
+    assert_eq!(name.art_file(" fire.art "), "art\\eye_candy\\fire.art");
+    assert_eq!(
+        art_loader_settings(art_id).blend_mode,
+        bevy_art::ArtBlendMode::Additive
+    );
+    let interface = TigArtId::new(ArtType::Interface, 327).unwrap();
+    assert_eq!(
+        art_loader_settings(interface).blend_mode,
+        bevy_art::ArtBlendMode::Alpha
+    );
+    assert_eq!(Name::Roof.art_file("rfwood 1"), "art\\roof\\rfwood.art");
+    assert_eq!(tile_file("grs", "grs", 2), "art\\tile\\grsbse2.art");
+    assert_eq!(tile_file("grs", "drt", 0), "art\\tile\\grsdrt0.art");
+    assert_eq!(Name::WallProto.art_file("brick"), "art\\wall\\brick.art");
+    assert_eq!(
+        Name::Structure.art_file("tower 3"),
+        "art\\structure\\tower.art"
+    );
+    assert_eq!(Name::from_art_type(ArtType::Critter), None);
+}
@@ -1,4 +1,6 @@
+mod dat_files;
 mod dat_repo;
 
 pub use bevy_dat::Dat;
+pub use dat_files::*;
 pub use dat_repo::*;
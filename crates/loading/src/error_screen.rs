@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use common::state::AppState;
+use config::{DATA_DIR_CONFIG, DATA_DIR_ENV, DATA_DIR_FLAG, DataDir};
+
+use crate::{DAT_GROUP, LoadManifest};
+
+pub(crate) fn setup_error_screen(
+    manifest: Res<LoadManifest>,
+    data_dir: Res<DataDir>,
+    mut commands: Commands,
+) {
+    let (dats, others): (Vec<_>, Vec<_>) = manifest
+        .failures()
+        .iter()
+        .partition(|failure| failure.group == DAT_GROUP);
+    let (missing, corrupt): (Vec<_>, Vec<_>) =
+        dats.into_iter().partition(|failure| failure.missing);
+
+    let mut message = String::new();
+    if !missing.is_empty() {
+        message.push_str("Required game archives are missing:\n");
+        for failure in &missing {
+            message.push_str(&format!("    {}: {}\n", failure.path, failure.error));
+        }
+        message.push_str(&format!(
+            "\nLooked in {}, set by {}.\n\
+             Point to your Arcanum installation with {DATA_DIR_FLAG} <path>, the {DATA_DIR_ENV}\n\
+             environment variable or a 'data dir=<path>' line in {DATA_DIR_CONFIG}.\n\n",
+            data_dir.path.display(),
+            data_dir.source
+        ));
+    }
+    if !corrupt.is_empty() {
+        message.push_str("Game archives could not be read, they may be damaged:\n");
+        for failure in &corrupt {
+            message.push_str(&format!("    {}: {}\n", failure.path, failure.error));
+        }
+        message.push_str("\nReinstall or verify the files of your Arcanum installation.\n\n");
+    }
+    if !others.is_empty() {
+        message.push_str("Could not load the game data:\n");
+        for failure in &others {
+            message.push_str(&format!(
+                "    {} {}: {}\n",
+                failure.group, failure.path, failure.error
+            ));
+        }
+    }
+    commands.spawn((
+        Node {
+            padding: UiRect::all(Val::Px(20.)),
+            ..default()
+        },
+        Text::new(message),
+        TextFont::from_font_size(14.),
+        StateScoped(AppState::Error),
+    ));
+}
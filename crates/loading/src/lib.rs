@@ -1,3 +1,6 @@
+mod error_screen;
+mod load_manifest;
 mod loading;
 
+pub use load_manifest::{DAT_GROUP, LoadFailure, LoadFinished, LoadManifest, LoadProgress};
 pub use loading::*;
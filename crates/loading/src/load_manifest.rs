@@ -0,0 +1,272 @@
+use bevy::{
+    asset::{AssetLoadError, RecursiveDependencyLoadState, io::AssetReaderError},
+    prelude::*,
+};
+use common::state::AppState;
+
+/// Group of the dats, the error screen explains how to install them.
+pub const DAT_GROUP: &str = "dats";
+
+/// Assets the current phase of the game is waiting for, grouped by what they are (dats, mes, fonts, ...).
+/// Everything added is loaded concurrently, [`track_manifest`] reports the progress through [`LoadProgress`]
+/// and sends [`LoadFinished`] once all of it is loaded, or right after entering a state that added nothing. A single failure of a required asset, such as
+/// the dats or the core interface art, switches to [`AppState::Error`]. Optional ones only log a warning.
+#[derive(Default, Resource)]
+pub struct LoadManifest {
+    entries: Vec<LoadEntry>,
+    failures: Vec<LoadFailure>,
+}
+
+struct LoadEntry {
+    group: &'static str,
+    path: String,
+    handle: UntypedHandle,
+    optional: bool,
+    loaded: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LoadFailure {
+    pub group: &'static str,
+    pub path: String,
+    pub error: String,
+    /// Whether the asset doesn't exist, as opposed to existing but failing to load.
+    pub missing: bool,
+}
+
+/// Sent whenever an asset of the [`LoadManifest`] finished loading.
+#[derive(Clone, Debug, Event)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+    /// An asset that is still loading.
+    pub current: Option<String>,
+}
+
+impl LoadProgress {
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 1.,
+            total => self.loaded as f32 / total as f32,
+        }
+    }
+}
+
+/// Sent once every asset of the [`LoadManifest`] is loaded, the manifest is empty again afterwards.
+#[derive(Clone, Debug, Event)]
+pub struct LoadFinished;
+
+impl LoadManifest {
+    pub fn add(&mut self, group: &'static str, handle: impl Into<UntypedHandle>) {
+        self.push(group, handle.into(), false);
+    }
+
+    /// Adds an asset the game can do without, failing to load it only logs a warning.
+    pub fn add_optional(&mut self, group: &'static str, handle: impl Into<UntypedHandle>) {
+        self.push(group, handle.into(), true);
+    }
+
+    fn push(&mut self, group: &'static str, handle: UntypedHandle, optional: bool) {
+        let path = handle
+            .path()
+            .map_or_else(|| format!("{:?}", handle.id()), ToString::to_string);
+        self.entries.push(LoadEntry {
+            group,
+            path,
+            handle,
+            optional,
+            loaded: false,
+        });
+    }
+
+    /// Records an asset that couldn't even be requested, e.g. because it is missing from the dats.
+    pub fn fail(&mut self, group: &'static str, path: impl Into<String>, error: impl ToString) {
+        self.failures.push(LoadFailure {
+            group,
+            path: path.into(),
+            error: error.to_string(),
+            missing: true,
+        });
+    }
+
+    /// Like [`LoadManifest::fail`] for an optional asset, which only logs a warning.
+    pub fn skip(&self, group: &'static str, path: impl AsRef<str>, error: impl ToString) {
+        warn!(
+            "skipping optional {group} {}: {}",
+            path.as_ref(),
+            error.to_string()
+        );
+    }
+
+    pub fn failures(&self) -> &[LoadFailure] {
+        &self.failures
+    }
+
+    /// Whether everything added so far is loaded without failures. A [`LoadFinished`] left over
+    /// from a previous phase arrives while this is still `false` for the next one.
+    pub fn is_done(&self) -> bool {
+        self.failures.is_empty() && self.entries.iter().all(|entry| entry.loaded)
+    }
+
+    /// Checks the assets that are still loading and returns whether any of them finished.
+    /// Required assets that failed to load are recorded as failures.
+    fn update(
+        &mut self,
+        load_state: impl Fn(&UntypedHandle) -> RecursiveDependencyLoadState,
+    ) -> bool {
+        let mut changed = false;
+        let LoadManifest { entries, failures } = self;
+        for entry in entries.iter_mut().filter(|entry| !entry.loaded) {
+            match load_state(&entry.handle) {
+                RecursiveDependencyLoadState::Loaded => {
+                    entry.loaded = true;
+                    changed = true;
+                }
+                RecursiveDependencyLoadState::Failed(error) if entry.optional => {
+                    warn!("skipping optional {} {}: {error}", entry.group, entry.path);
+                    entry.loaded = true;
+                    changed = true;
+                }
+                RecursiveDependencyLoadState::Failed(error) => {
+                    entry.loaded = true;
+                    changed = true;
+                    failures.push(LoadFailure {
+                        group: entry.group,
+                        path: entry.path.clone(),
+                        error: error.to_string(),
+                        missing: matches!(
+                            *error,
+                            AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_))
+                        ),
+                    });
+                }
+                _ => {}
+            }
+        }
+        changed
+    }
+
+    pub fn progress(&self) -> LoadProgress {
+        LoadProgress {
+            loaded: self.entries.iter().filter(|entry| entry.loaded).count(),
+            total: self.entries.len(),
+            current: self
+                .entries
+                .iter()
+                .find(|entry| !entry.loaded)
+                .map(|entry| entry.path.clone()),
+        }
+    }
+}
+
+pub(crate) fn track_manifest(
+    mut manifest: ResMut<LoadManifest>,
+    asset_server: Res<AssetServer>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut progress: EventWriter<LoadProgress>,
+    mut finished: EventWriter<LoadFinished>,
+) {
+    if *state.get() == AppState::Error {
+        return;
+    }
+    if manifest.entries.is_empty() && manifest.failures.is_empty() {
+        // a state with nothing to load is done as soon as it is entered
+        if state.is_changed() {
+            finished.write(LoadFinished);
+        }
+        return;
+    }
+
+    let changed = manifest.update(|handle| asset_server.recursive_dependency_load_state(handle));
+
+    if !manifest.failures.is_empty() {
+        for failure in &manifest.failures {
+            error!(
+                "could not load {} {}: {}",
+                failure.group, failure.path, failure.error
+            );
+        }
+        next_state.set(AppState::Error);
+        return;
+    }
+    if changed {
+        progress.write(manifest.progress());
+    }
+    if manifest.entries.iter().all(|entry| entry.loaded) {
+        manifest.entries.clear();
+        finished.write(LoadFinished);
+    }
+}
+
+#[test]
+fn test_progress_and_failures() {
+    use std::sync::Arc;
+
+    let mut images = Assets::<Image>::default();
+    let [dat, strings, font] = [(); 3].map(|_| images.add(Image::default()));
+    let mut manifest = LoadManifest::default();
+    manifest.add(DAT_GROUP, dat.clone());
+    manifest.add_optional("strings", strings.clone());
+    manifest.add("fonts", font.clone());
+    manifest.skip("strings", "mes\\description.mes", "not in the dats");
+    assert!(manifest.failures().is_empty());
+
+    let failed =
+        || RecursiveDependencyLoadState::Failed(Arc::new(AssetLoadError::AssetMetaReadError));
+    assert!(!manifest.update(|_| RecursiveDependencyLoadState::Loading));
+    assert_eq!(manifest.progress().loaded, 0);
+
+    // an optional asset that failed counts as loaded
+    let changed = manifest.update(|handle| match handle.id() {
+        id if id == dat.id().untyped() => RecursiveDependencyLoadState::Loaded,
+        id if id == strings.id().untyped() => failed(),
+        _ => RecursiveDependencyLoadState::Loading,
+    });
+    assert!(changed);
+    let progress = manifest.progress();
+    assert_eq!((progress.loaded, progress.total), (2, 3));
+    assert_eq!(progress.current, Some(format!("{:?}", font.id().untyped())));
+    assert!(manifest.failures().is_empty() && !manifest.is_done());
+
+    manifest.update(|_| failed());
+    let groups: Vec<_> = manifest
+        .failures()
+        .iter()
+        .map(|failure| failure.group)
+        .collect();
+    assert_eq!(groups, ["fonts"]);
+    assert!(!manifest.is_done());
+}
+
+#[test]
+fn test_nothing_to_load() {
+    use bevy::state::app::StatesPlugin;
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+        .init_state::<AppState>()
+        .init_resource::<LoadManifest>()
+        .add_event::<LoadProgress>()
+        .add_event::<LoadFinished>()
+        .add_systems(PreUpdate, track_manifest);
+    let finished = |app: &mut App| {
+        app.world_mut()
+            .resource_mut::<Events<LoadFinished>>()
+            .drain()
+            .count()
+    };
+
+    app.update();
+    assert_eq!(finished(&mut app), 1);
+    app.update();
+    assert_eq!(finished(&mut app), 0);
+
+    // states change after PreUpdate, so the next frame sees the new one
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::MainMenu);
+    app.update();
+    app.update();
+    assert_eq!(finished(&mut app), 1);
+}
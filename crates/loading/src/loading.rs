@@ -1,114 +1,198 @@
 use bevy::prelude::*;
+use bevy_image_font::ImageFont;
 use bevy_mes::Mes;
 use common::state::AppState;
-use dat_repo::{Dat, DatRepo, MesCritterType, MesFileType, Name, Portrait};
+use dat_repo::{DatLayer, DatRepo, DatRepoLoadingError, MesCritterType, MesFileType, Name};
+use localization::Strings;
+use rules::XpTable;
+
+use crate::{
+    LoadFinished, LoadManifest, LoadProgress, error_screen::setup_error_screen,
+    load_manifest::track_manifest,
+};
+
+const SPLASH: &str = "art\\splash\\Splash1.bmp";
+
+/// Interface art of the font used by the main menu. The number comes from the first version of the
+/// main menu, which loaded entry 327 of `art\interface\interface.mes`, and is not verified against the game.
+pub const INTERFACE_FONT: u32 = 327;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(AppState::Loading), setup)
-        .add_systems(Update, load_repo.run_if(in_state(AppState::Loading)));
+    app.init_resource::<LoadManifest>()
+        .init_resource::<MesHandles>()
+        .add_event::<LoadProgress>()
+        .add_event::<LoadFinished>()
+        .add_systems(PreUpdate, track_manifest)
+        .add_systems(OnEnter(AppState::Loading), setup)
+        .add_systems(
+            Update,
+            (update_progress_bar, finish_loading).run_if(in_state(AppState::Loading)),
+        )
+        .add_systems(OnEnter(AppState::Error), setup_error_screen);
 }
 
-fn setup(mut repo: ResMut<DatRepo>, asset_server: Res<AssetServer>, mut commands: Commands) {
+#[derive(Component)]
+struct ProgressBar;
+
+#[derive(Component)]
+struct ProgressFile;
+
+#[derive(Default, Resource)]
+struct MesHandles(Vec<(MesFileType, Handle<Mes>)>);
+
+fn setup(
+    repo: Res<DatRepo>,
+    mut strings: ResMut<Strings>,
+    mut manifest: ResMut<LoadManifest>,
+    asset_server: Res<AssetServer>,
+    mut mes_handles: ResMut<MesHandles>,
+    mut commands: Commands,
+) {
     info!("loading now");
-    let path = repo.load_file_directly("art\\splash\\Splash1.bmp").unwrap();
-    commands.spawn((
-        ImageNode {
-            image: asset_server.load(path),
-            ..default()
-        },
-        Node {
-            width: Val::Percent(100.),
-            align_self: AlignSelf::Center,
-            justify_self: JustifySelf::Center,
-            ..default()
-        },
-        StateScoped(AppState::Loading),
-    ));
+    let image = match repo.load_file_directly(SPLASH) {
+        Ok(path) => {
+            let handle = asset_server.load(path);
+            manifest.add_optional("art", handle.clone());
+            handle
+        }
+        Err(err) => {
+            manifest.skip("art", SPLASH, err);
+            Handle::default()
+        }
+    };
+    commands
+        .spawn((
+            ImageNode { image, ..default() },
+            Node {
+                width: Val::Percent(100.),
+                align_self: AlignSelf::Center,
+                justify_self: JustifySelf::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            StateScoped(AppState::Loading),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Percent(8.),
+                        width: Val::Percent(60.),
+                        height: Val::Px(12.),
+                        border: UiRect::all(Val::Px(1.)),
+                        ..default()
+                    },
+                    BorderColor(Color::srgb(0.6, 0.5, 0.3)),
+                    BackgroundColor(Color::BLACK),
+                ))
+                .with_child((
+                    Node {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.55, 0.1, 0.05)),
+                    ProgressBar,
+                ));
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(4.),
+                    ..default()
+                },
+                Text::default(),
+                TextFont::from_font_size(12.),
+                ProgressFile,
+            ));
+        });
+
+    for key in MesFileType::ALL {
+        if !key.is_strings() {
+            // the interface art names are needed to find the core interface art
+            let required = key == MesFileType::Name(Name::Interface);
+            match repo.load_file_directly(key.path()) {
+                Ok(path) => {
+                    let handle = asset_server.load(path);
+                    match required {
+                        true => manifest.add("mes", handle.clone()),
+                        false => manifest.add_optional("mes", handle.clone()),
+                    }
+                    mes_handles.0.push((key, handle));
+                }
+                Err(err) if required => manifest.fail("mes", key.path(), err),
+                Err(err) => manifest.skip("mes", key.path(), err),
+            }
+            continue;
+        }
+        // string tables keep the version of every layer, see `Strings`
+        let mut found = false;
+        for layer in [DatLayer::Module, DatLayer::Base] {
+            if let Ok(path) = repo.load_file_from(key.path(), layer) {
+                let handle = asset_server.load(path);
+                manifest.add_optional("strings", handle.clone());
+                strings.add(layer.into(), key, handle);
+                found = true;
+            }
+        }
+        if !found {
+            manifest.skip(
+                "strings",
+                key.path(),
+                DatRepoLoadingError::FileNotFound(key.path().to_string()),
+            );
+        }
+    }
 
-    let mes = [
-        ("mes\\description.mes", MesFileType::Description),
-        ("mes\\item_effect.mes", MesFileType::ItemEffect),
-        (
-            "rules\\xp_critter.mes",
-            MesFileType::Critter(MesCritterType::Xp),
-        ),
-        (
-            "mes\\critter.mes",
-            MesFileType::Critter(MesCritterType::Base),
-        ),
-        (
-            "art\\scenery\\scenery.mes",
-            MesFileType::Name(Name::Scenery),
-        ),
-        (
-            "art\\interface\\interface.mes",
-            MesFileType::Name(Name::Interface),
-        ),
-        (
-            "art\\unique_npc\\unique_npc.mes",
-            MesFileType::Name(Name::UniqueNpc),
-        ),
-        (
-            "art\\monster\\monster.mes",
-            MesFileType::Name(Name::Monster),
-        ),
-        (
-            "art\\eye_candy\\eye_candy.mes",
-            MesFileType::Name(Name::EyeCandy),
-        ),
-        (
-            "art\\container\\container.mes",
-            MesFileType::Name(Name::Container),
-        ),
-        ("art\\light\\light.mes", MesFileType::Name(Name::Light)),
-        ("art\\tile\\tilename.mes", MesFileType::Name(Name::Tile)),
-        ("art\\roof\\roofname.mes", MesFileType::Name(Name::Roof)),
-        ("art\\wall\\wallname.mes", MesFileType::Name(Name::Wall)),
-        (
-            "art\\wall\\wallproto.mes",
-            MesFileType::Name(Name::WallProto),
-        ),
-        (
-            "art\\structure\\structure.mes",
-            MesFileType::Name(Name::Structure),
-        ),
-        (
-            "portrait\\gameport.mes",
-            MesFileType::Portrait(Portrait::Game),
-        ),
-        (
-            "portrait\\userport.mes",
-            MesFileType::Portrait(Portrait::User),
-        ),
-    ];
-    for (pattern, key) in mes {
-        repo.add_mes_file_to_load(key, pattern);
+    match repo.load_interface_art(INTERFACE_FONT) {
+        Ok(path) => manifest.add("fonts", asset_server.load::<ImageFont>(path)),
+        Err(err) => manifest.fail("fonts", INTERFACE_FONT.to_string(), err),
     }
     info!("loading on enter done");
 }
 
-fn load_repo(
-    mut dat_repo: ResMut<DatRepo>,
-    mes: Res<Assets<Mes>>,
-    asset_server: Res<AssetServer>,
-    mut next_state: ResMut<NextState<AppState>>,
+fn update_progress_bar(
+    mut events: EventReader<LoadProgress>,
+    mut bar: Query<&mut Node, With<ProgressBar>>,
+    mut file: Query<&mut Text, With<ProgressFile>>,
 ) {
-    if dat_repo.load_next(&asset_server) {
+    let Some(progress) = events.read().last() else {
         return;
+    };
+    for mut node in &mut bar {
+        node.width = Val::Percent(progress.fraction() * 100.);
+    }
+    for mut text in &mut file {
+        text.0 = progress.current.clone().unwrap_or_default();
     }
+}
 
-    if !dat_repo
-        .mes_handles
-        .iter()
-        .all(|(_, handle)| asset_server.is_loaded(handle))
-    {
+fn finish_loading(
+    mut events: EventReader<LoadFinished>,
+    mut dat_repo: ResMut<DatRepo>,
+    manifest: Res<LoadManifest>,
+    mut mes_handles: ResMut<MesHandles>,
+    mes: Res<Assets<Mes>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    if events.read().last().is_none() || !manifest.is_done() {
         return;
     }
-    while let Some((mes_type, handle)) = dat_repo.mes_handles.pop() {
-        if let Some(mes) = mes.get(&handle) {
-            dat_repo.insert_mes(mes_type, mes);
+    for (mes_type, handle) in std::mem::take(&mut mes_handles.0) {
+        let Some(mes) = mes.get(&handle) else {
+            continue;
+        };
+        if mes_type == MesFileType::Critter(MesCritterType::Xp) {
+            match XpTable::from_mes(mes) {
+                Ok(table) => commands.insert_resource(table),
+                Err(err) => manifest.skip("rules", mes_type.path(), err),
+            }
         }
+        dat_repo.insert_mes(mes_type, mes);
+    }
+    if manifest.failures().is_empty() {
+        info!("done");
+        next_state.set(AppState::MainMenu);
     }
-    info!("done");
-    next_state.set(AppState::MainMenu);
 }
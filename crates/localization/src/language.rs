@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{StringLayer, Strings};
+
+/// Language of the [`StringLayer::Fallback`] layer.
+pub const FALLBACK_LANGUAGE: &str = "english";
+
+/// Language the game is displayed in. Translations are loose `.mes` files in `lang/<language>/`,
+/// laid out like inside the dats, e.g. `lang/german/mes/description.mes`.
+/// Changing this resource swaps the [`StringLayer::Translation`] layer of [`Strings`] at runtime.
+#[derive(Clone, Debug, Eq, PartialEq, Resource)]
+pub struct Language(pub String);
+
+impl Default for Language {
+    fn default() -> Self {
+        Language(FALLBACK_LANGUAGE.to_string())
+    }
+}
+
+pub(crate) fn load_fallback(mut strings: ResMut<Strings>, asset_server: Res<AssetServer>) {
+    strings.add_folder(
+        StringLayer::Fallback,
+        asset_server.load_folder(format!("lang/{FALLBACK_LANGUAGE}")),
+    );
+}
+
+pub(crate) fn switch_language(
+    language: Res<Language>,
+    mut strings: ResMut<Strings>,
+    asset_server: Res<AssetServer>,
+) {
+    info!("switching language to {}", language.0);
+    strings.clear(StringLayer::Translation);
+    if language.0 != FALLBACK_LANGUAGE {
+        strings.add_folder(
+            StringLayer::Translation,
+            asset_server.load_folder(format!("lang/{}", language.0)),
+        );
+    }
+}
@@ -0,0 +1,9 @@
+mod language;
+mod localization;
+mod localized_text;
+mod strings;
+
+pub use language::{FALLBACK_LANGUAGE, Language};
+pub use localization::plugin;
+pub use localized_text::LocalizedText;
+pub use strings::{StringLayer, Strings};
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+use crate::{
+    Language, Strings,
+    language::{load_fallback, switch_language},
+    localized_text::localize_text,
+    strings::{resolve_folders, sync_strings},
+};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Strings>()
+        .init_resource::<Language>()
+        .add_systems(Startup, load_fallback)
+        .add_systems(
+            Update,
+            (
+                switch_language.run_if(resource_changed::<Language>),
+                resolve_folders,
+                sync_strings,
+                localize_text,
+            )
+                .chain(),
+        );
+}
@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use bevy_image_font::ImageText;
+use dat_repo::MesFileType;
+
+use crate::Strings;
+
+/// Fills the [`ImageText`] of its entity with a string from [`Strings`]
+/// and updates it whenever the strings change, e.g. after switching the [`crate::Language`].
+#[derive(Clone, Component, Debug)]
+#[require(ImageText)]
+pub struct LocalizedText {
+    pub file: MesFileType,
+    pub index: u32,
+}
+
+impl LocalizedText {
+    pub fn new(file: MesFileType, index: u32) -> Self {
+        Self { file, index }
+    }
+}
+
+pub(crate) fn localize_text(
+    strings: Res<Strings>,
+    mut query: Query<(Ref<LocalizedText>, &mut ImageText)>,
+) {
+    for (text, mut image_text) in &mut query {
+        if !strings.is_changed() && !text.is_changed() {
+            continue;
+        }
+        let Some(localized) = strings.get(text.file, text.index) else {
+            warn!("{:?} has no string {}", text.file, text.index);
+            continue;
+        };
+        if image_text.0 != localized {
+            image_text.0 = localized.to_string();
+        }
+    }
+}
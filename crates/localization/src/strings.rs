@@ -0,0 +1,200 @@
+use std::{collections::BTreeSet, path::Path};
+
+use bevy::{
+    asset::LoadedFolder,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_mes::Mes;
+use dat_repo::{DatLayer, MesFileType};
+
+/// Sources of strings, from the most to the least specific one.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StringLayer {
+    /// Loose files of the current [`crate::Language`].
+    Translation,
+    /// Files in the dats of the running module.
+    Module,
+    /// Files in the dats of the base game.
+    Base,
+    /// Loose files of [`crate::FALLBACK_LANGUAGE`], for everything the other layers are missing.
+    Fallback,
+}
+
+impl StringLayer {
+    pub const ALL: [StringLayer; 4] = [
+        StringLayer::Translation,
+        StringLayer::Module,
+        StringLayer::Base,
+        StringLayer::Fallback,
+    ];
+}
+
+impl From<DatLayer> for StringLayer {
+    fn from(value: DatLayer) -> Self {
+        match value {
+            DatLayer::Module => StringLayer::Module,
+            DatLayer::Base => StringLayer::Base,
+        }
+    }
+}
+
+/// Every string of the game, looked up by file and index.
+/// A lookup returns the entry of the most specific [`StringLayer`] that has it,
+/// so a module only has to contain the strings it changes and missing translations fall back to English.
+#[derive(Default, Resource)]
+pub struct Strings {
+    tables: HashMap<(StringLayer, MesFileType), Mes>,
+    handles: Vec<(StringLayer, MesFileType, Handle<Mes>)>,
+    folders: Vec<(StringLayer, Handle<LoadedFolder>)>,
+}
+
+impl Strings {
+    pub fn get(&self, file: MesFileType, index: u32) -> Option<&str> {
+        StringLayer::ALL
+            .iter()
+            .find_map(|layer| self.tables.get(&(*layer, file))?.get(index))
+    }
+
+    /// Optional text between index and text field, `{100}middle{Hello}`, of the entry [`Strings::get`] returns.
+    pub fn middle(&self, file: MesFileType, index: u32) -> Option<&str> {
+        let mes = StringLayer::ALL.iter().find_map(|layer| {
+            self.tables
+                .get(&(*layer, file))
+                .filter(|mes| mes.get(index).is_some())
+        })?;
+        mes.middle(index)
+    }
+
+    /// Indices of `file` in any layer, in ascending order.
+    pub fn indices(&self, file: MesFileType) -> impl Iterator<Item = u32> + use<> {
+        StringLayer::ALL
+            .iter()
+            .filter_map(|layer| self.tables.get(&(*layer, file)))
+            .flat_map(|mes| mes.iter().map(|(index, _)| index))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Adds a file to a layer. Its strings become available once it is loaded and follow hot reloads.
+    /// Only files that [`MesFileType::is_strings`] belong here.
+    pub fn add(&mut self, layer: StringLayer, file: MesFileType, handle: Handle<Mes>) {
+        self.handles.push((layer, file, handle));
+    }
+
+    /// Adds every file of a folder whose path inside it matches the [`MesFileType::path`] of a string table.
+    pub fn add_folder(&mut self, layer: StringLayer, folder: Handle<LoadedFolder>) {
+        self.folders.push((layer, folder));
+    }
+
+    /// Removes every file of a layer.
+    pub fn clear(&mut self, layer: StringLayer) {
+        self.tables.retain(|(l, _), _| *l != layer);
+        self.handles.retain(|(l, _, _)| *l != layer);
+        self.folders.retain(|(l, _)| *l != layer);
+    }
+}
+
+/// Turns loaded folders into files, see [`Strings::add_folder`].
+pub(crate) fn resolve_folders(
+    mut strings: ResMut<Strings>,
+    folders: Res<Assets<LoadedFolder>>,
+    asset_server: Res<AssetServer>,
+) {
+    if strings.folders.is_empty() {
+        return;
+    }
+    // only the tables are observable, so bookkeeping doesn't count as a change
+    let strings = strings.bypass_change_detection();
+    for (layer, handle) in std::mem::take(&mut strings.folders) {
+        let Some(folder) = folders.get(&handle) else {
+            if asset_server.load_state(&handle).is_failed() {
+                warn!("no {layer:?} strings, could not load {:?}", handle.path());
+            } else {
+                strings.folders.push((layer, handle));
+            }
+            continue;
+        };
+        let root = handle.path().map(|path| path.path().to_path_buf());
+        for untyped in &folder.handles {
+            let Ok(mes) = untyped.clone().try_typed::<Mes>() else {
+                continue;
+            };
+            let Some(path) = untyped.path() else {
+                continue;
+            };
+            let relative = root
+                .as_deref()
+                .and_then(|root| path.path().strip_prefix(root).ok())
+                .unwrap_or(path.path());
+            match mes_file_type(relative) {
+                Some(file) => strings.handles.push((layer, file, mes)),
+                None => warn!("{path} is not a known string file"),
+            }
+        }
+    }
+}
+
+/// Copies the contents of newly loaded and modified files into the tables.
+pub(crate) fn sync_strings(
+    mut events: EventReader<AssetEvent<Mes>>,
+    mes: Res<Assets<Mes>>,
+    mut strings: ResMut<Strings>,
+) {
+    let modified: HashSet<AssetId<Mes>> = events
+        .read()
+        .filter_map(|event| match *event {
+            AssetEvent::Modified { id } => Some(id),
+            _ => None,
+        })
+        .collect();
+    let updates: Vec<_> = strings
+        .handles
+        .iter()
+        .filter(|(layer, file, handle)| {
+            !strings.tables.contains_key(&(*layer, *file)) || modified.contains(&handle.id())
+        })
+        .filter_map(|(layer, file, handle)| Some(((*layer, *file), mes.get(handle)?.clone())))
+        .collect();
+    if !updates.is_empty() {
+        strings.tables.extend(updates);
+    }
+}
+
+/// Matches loose files case insensitively and with either path separator, like the dats do.
+fn mes_file_type(path: &Path) -> Option<MesFileType> {
+    let normalize = |path: &str| path.replace('\\', "/").to_lowercase();
+    let path = normalize(&path.to_string_lossy());
+    MesFileType::ALL
+        .into_iter()
+        .filter(|file| file.is_strings())
+        .find(|file| normalize(file.path()) == path)
+}
+
+#[test]
+fn test_fallback_chain() {
+    let mut strings = Strings::default();
+    let mut insert = |layer, contents| {
+        let mes = Mes::from_contents(contents).unwrap();
+        strings
+            .tables
+            .insert((layer, MesFileType::Description), mes);
+    };
+    insert(StringLayer::Fallback, "{1}{one}\n{2}{two}\n{3}{three}");
+    insert(StringLayer::Base, "{1}{eins}\n{2}{zwei}");
+    insert(StringLayer::Module, "{1}{Eins}");
+
+    assert_eq!(strings.get(MesFileType::Description, 1), Some("Eins"));
+    assert_eq!(strings.get(MesFileType::Description, 2), Some("zwei"));
+    assert_eq!(strings.get(MesFileType::Description, 3), Some("three"));
+    assert_eq!(strings.get(MesFileType::Description, 4), None);
+    assert_eq!(strings.get(MesFileType::ItemEffect, 1), None);
+
+    strings.clear(StringLayer::Module);
+    assert_eq!(strings.get(MesFileType::Description, 1), Some("eins"));
+    assert_eq!(
+        mes_file_type(Path::new("MES/Description.mes")),
+        Some(MesFileType::Description)
+    );
+    assert_eq!(mes_file_type(Path::new("art/tile/tilename.mes")), None);
+}
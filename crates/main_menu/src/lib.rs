@@ -1,3 +1,5 @@
 mod main_menu;
+mod menu_tree;
 
-pub use main_menu::plugin;
+pub use main_menu::{MainMenu, plugin};
+pub use menu_tree::*;
@@ -1,20 +1,94 @@
-use bevy::prelude::*;
-use bevy_image_font::{ImageFont, ImageText, ImageTextFont};
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use bevy_image_font::{
+    ImageFont, ImageText, ImageTextFont, ImageTextInput, ImageTextInputSystems, text_input_focused,
+};
 use common::state::AppState;
-use dat_repo::DatRepo;
+use dat_repo::{DatRepo, MesFileType};
+use loading::{INTERFACE_FONT, LoadManifest};
+use localization::Strings;
+
+use crate::{MenuAction, MenuId, MenuTree};
+
+/// Interface art of the menu background. Like [`INTERFACE_FONT`] the number comes from the first version
+/// of this menu, which loaded entry 329 of `art\interface\interface.mes`, and is not verified against the game.
+const BACKGROUND: u32 = 329;
+
+/// Tint of the entry under the mouse.
+const HIGHLIGHT: Color = Color::srgb(1., 0.8, 0.);
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(AppState::MainMenu), setup);
+    app.add_systems(OnEnter(AppState::MainMenu), setup)
+        .add_systems(
+            Update,
+            (
+                select_entries.before(ImageTextInputSystems),
+                highlight_entries,
+                show_menu.run_if(resource_changed::<MainMenu>),
+            )
+                .chain()
+                .run_if(in_state(AppState::MainMenu)),
+        );
 }
 
-fn setup(dat_repo: Res<DatRepo>, asset_server: Res<AssetServer>, mut commands: Commands) {
+/// The menus read from `MainMenu.mes` and the one that is shown.
+#[derive(Resource)]
+pub struct MainMenu {
+    pub tree: MenuTree,
+    pub current: MenuId,
+    font: Handle<ImageFont>,
+}
+
+/// Holds the entries of the current menu.
+#[derive(Component)]
+struct MenuEntries;
+
+#[derive(Component)]
+struct MenuButton(Option<MenuAction>);
+
+fn setup(
+    dat_repo: Res<DatRepo>,
+    strings: Res<Strings>,
+    mut manifest: ResMut<LoadManifest>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
     info!("main menu");
-    let path = dat_repo.load_file_by_num(329).unwrap();
-    let font = dat_repo.load_file_by_num(327).unwrap();
+    let (path, font) = match (
+        dat_repo.load_interface_art(BACKGROUND),
+        dat_repo.load_interface_art(INTERFACE_FONT),
+    ) {
+        (Ok(path), Ok(font)) => (path, font),
+        (Err(err), _) | (_, Err(err)) => {
+            manifest.fail("art", "main menu interface", err);
+            return;
+        }
+    };
+
+    let tree = MenuTree::new(strings.indices(MesFileType::MainMenu), |index| {
+        Some((
+            strings.get(MesFileType::MainMenu, index)?,
+            strings.middle(MesFileType::MainMenu, index),
+        ))
+    });
+    if tree.entries(MenuId::MAIN).is_empty() {
+        warn!("{} has no main menu entries", MesFileType::MainMenu.path());
+    }
+    commands.insert_resource(MainMenu {
+        tree,
+        current: MenuId::MAIN,
+        font: asset_server.load(&font),
+    });
+
     commands
         .spawn((
             ImageNode {
-                image: asset_server.load(path),
+                image: asset_server.load(format!("{path}#image")),
                 ..default()
             },
             Node {
@@ -27,26 +101,96 @@ fn setup(dat_repo: Res<DatRepo>, asset_server: Res<AssetServer>, mut commands: C
             },
             StateScoped(AppState::MainMenu),
         ))
-        .with_children(|parent| {
-            // parent.spawn((ImageNode {
-            //     image: asset_server.load(&font),
-            //     ..default()
-            // },
-            //     Node {
-            //         align_self: AlignSelf::Center,
-            //         justify_self: JustifySelf::Center,
-            //         ..default()
-            //     },));
-            parent.spawn((
-                ImageText::new("Hallo Nico"),
-                ImageTextFont {
-                    font: asset_server.load(&font),
-                },
-                Node {
-                    align_self: AlignSelf::Center,
-                    justify_self: JustifySelf::Center,
-                    ..default()
-                },
-            ));
-        });
+        .with_child((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_self: AlignSelf::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.),
+                ..default()
+            },
+            MenuEntries,
+        ));
+}
+
+/// Replaces the shown entries with those of the current menu.
+fn show_menu(
+    menu: Res<MainMenu>,
+    containers: Query<Entity, With<MenuEntries>>,
+    mut commands: Commands,
+) {
+    for container in &containers {
+        commands
+            .entity(container)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for entry in menu.tree.entries(menu.current) {
+                    parent.spawn((
+                        ImageText::new(entry.label.replace('[', "[[")),
+                        ImageTextFont::new(menu.font.clone()),
+                        Button,
+                        MenuButton(entry.action),
+                    ));
+                }
+            });
+    }
+}
+
+/// Runs the action of a clicked entry or of a typed hotkey, escape goes back to the menu above.
+/// Keys are left to a focused text input.
+fn select_entries(
+    mut keyboard: EventReader<KeyboardInput>,
+    inputs: Query<&ImageTextInput>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut menu: ResMut<MainMenu>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let typing = text_input_focused(inputs);
+    let typed: Vec<_> = keyboard
+        .read()
+        .filter(|event| !typing && event.state == ButtonState::Pressed)
+        .filter_map(|event| match &event.logical_key {
+            Key::Escape => Some(Some(MenuAction::Back)),
+            Key::Character(text) => {
+                let key = text.chars().next()?;
+                Some(menu.tree.hotkey(menu.current, key)?.action)
+            }
+            _ => None,
+        })
+        .collect();
+    let clicked = buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.0);
+    let Some(action) = clicked.chain(typed).next() else {
+        return;
+    };
+    let Some(action) = action else {
+        info!("this entry has no known action");
+        return;
+    };
+
+    match action {
+        MenuAction::Open(id) => menu.current = id,
+        MenuAction::Back => {
+            if let Some(parent) = menu.tree.parent(menu.current) {
+                menu.current = parent;
+            }
+        }
+        MenuAction::ViewIntro => next_state.set(AppState::teaser_video()),
+        MenuAction::Exit => {
+            exit.write(AppExit::Success);
+        }
+        other => info!("{other:?} is not implemented yet"),
+    }
+}
+
+fn highlight_entries(mut buttons: Query<(&Interaction, &mut ImageTextFont), Changed<Interaction>>) {
+    for (interaction, mut text_font) in &mut buttons {
+        text_font.color = match interaction {
+            Interaction::None => Color::WHITE,
+            Interaction::Hovered | Interaction::Pressed => HIGHLIGHT,
+        };
+    }
 }
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+/// What selecting a menu entry does.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MenuAction {
+    Open(MenuId),
+    /// Returns to the menu above, the main menu has none.
+    Back,
+    NewGame,
+    LoadGame,
+    LastSave,
+    ViewIntro,
+    Multiplayer,
+    Options,
+    Credits,
+    Exit,
+}
+
+/// Position of a menu in `MainMenu.mes`, where every run of consecutive indices is one menu.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MenuId(pub usize);
+
+impl MenuId {
+    pub const MAIN: MenuId = MenuId(0);
+    pub const SINGLE_PLAYER: MenuId = MenuId(1);
+}
+
+/// Actions of the entries of `MainMenu.mes` by index. The file only holds labels and hotkeys,
+/// so which entry does what has to be known here. The indices are not verified against the game's file yet.
+const LEAF_ACTIONS: [(u32, MenuAction); 9] = [
+    (1, MenuAction::Multiplayer),
+    (2, MenuAction::Options),
+    (3, MenuAction::Credits),
+    (4, MenuAction::Exit),
+    (100, MenuAction::NewGame),
+    (101, MenuAction::LoadGame),
+    (102, MenuAction::LastSave),
+    (103, MenuAction::ViewIntro),
+    (104, MenuAction::Back),
+];
+
+/// Entries that open a submenu, by index, and an index within the submenu they open.
+const SUBMENUS: [(u32, u32); 1] = [(0, 100)];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MenuEntry {
+    pub index: u32,
+    pub label: String,
+    /// Selects the entry when typed, taken from the text before the label, `{100}n{New Game}`.
+    pub hotkey: Option<char>,
+    /// What the entry does, `None` if it is not known.
+    pub action: Option<MenuAction>,
+}
+
+/// The main menu and its submenus, in the order of their indices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MenuTree {
+    menus: BTreeMap<MenuId, Vec<MenuEntry>>,
+}
+
+impl MenuTree {
+    /// Builds the tree from the indices of `MainMenu.mes` in ascending order and a lookup of text
+    /// and optional value by index, e.g. into [`localization::Strings`].
+    pub fn new<'a>(
+        indices: impl IntoIterator<Item = u32>,
+        lookup: impl Fn(u32) -> Option<(&'a str, Option<&'a str>)>,
+    ) -> MenuTree {
+        let mut runs: Vec<Vec<u32>> = Vec::new();
+        for index in indices {
+            match runs.last_mut() {
+                Some(run) if run.last().is_some_and(|last| last + 1 == index) => run.push(index),
+                _ => runs.push(vec![index]),
+            }
+        }
+
+        let menu_of = |index| runs.iter().position(|run| run.contains(&index)).map(MenuId);
+        let mut menus = BTreeMap::new();
+        for (menu, run) in runs.iter().enumerate() {
+            let mut entries = Vec::new();
+            for &index in run {
+                let Some((label, hotkey)) = lookup(index) else {
+                    continue;
+                };
+                let leaf = LEAF_ACTIONS.iter().find(|(i, _)| *i == index);
+                let submenu = SUBMENUS.iter().find(|(i, _)| *i == index);
+                let action = match (leaf, submenu) {
+                    (Some((_, action)), _) => Some(*action),
+                    (None, Some((_, target))) => menu_of(*target).map(MenuAction::Open),
+                    (None, None) => None,
+                };
+                entries.push(MenuEntry {
+                    index,
+                    label: label.to_string(),
+                    hotkey: hotkey
+                        .and_then(|hotkey| hotkey.trim().chars().next())
+                        .map(|hotkey| hotkey.to_ascii_lowercase()),
+                    action,
+                });
+            }
+            menus.insert(MenuId(menu), entries);
+        }
+        MenuTree { menus }
+    }
+
+    pub fn entries(&self, menu: MenuId) -> &[MenuEntry] {
+        self.menus.get(&menu).map_or(&[], Vec::as_slice)
+    }
+
+    /// Entry of `menu` whose hotkey is `key`, ignoring case.
+    pub fn hotkey(&self, menu: MenuId, key: char) -> Option<&MenuEntry> {
+        let key = key.to_ascii_lowercase();
+        self.entries(menu)
+            .iter()
+            .find(|entry| entry.hotkey == Some(key))
+    }
+
+    /// Menu containing the entry that opens `menu`.
+    pub fn parent(&self, menu: MenuId) -> Option<MenuId> {
+        self.menus.iter().find_map(|(id, entries)| {
+            entries
+                .iter()
+                .any(|entry| entry.action == Some(MenuAction::Open(menu)))
+                .then_some(*id)
+        })
+    }
+}
+
+#[test]
+fn test_menu_tree() {
+    let mes = bevy_mes::Mes::from_contents(
+        "{0}S{Single Player}\n\
+         {1}{Multiplayer}\n\
+         {2}o{Options}\n\
+         {3}{Credits}\n\
+         {4}X{Exit}\n\
+         {5}{Added by a module}\n\
+         {100}n{New Game}\n\
+         {101}{Load Game}\n\
+         {200}{unknown}",
+    )
+    .unwrap();
+    let tree = MenuTree::new(mes.iter().map(|(index, _)| index), |index| {
+        Some((mes.get(index)?, mes.middle(index)))
+    });
+
+    let labels = |menu| {
+        tree.entries(menu)
+            .iter()
+            .map(|entry| entry.label.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        labels(MenuId::MAIN),
+        [
+            "Single Player",
+            "Multiplayer",
+            "Options",
+            "Credits",
+            "Exit",
+            "Added by a module"
+        ]
+    );
+    assert_eq!(tree.entries(MenuId::MAIN)[5].action, None);
+    assert_eq!(labels(MenuId::SINGLE_PLAYER), ["New Game", "Load Game"]);
+    assert_eq!(
+        tree.hotkey(MenuId::MAIN, 's').map(|entry| entry.action),
+        Some(Some(MenuAction::Open(MenuId::SINGLE_PLAYER)))
+    );
+    assert_eq!(
+        tree.hotkey(MenuId::MAIN, 'x').map(|entry| entry.action),
+        Some(Some(MenuAction::Exit))
+    );
+    assert_eq!(
+        tree.entries(MenuId::SINGLE_PLAYER)[1].action,
+        Some(MenuAction::LoadGame)
+    );
+    assert_eq!(tree.entries(MenuId::SINGLE_PLAYER)[1].hotkey, None);
+    assert_eq!(tree.entries(MenuId(2))[0].action, None);
+    assert_eq!(tree.parent(MenuId::SINGLE_PLAYER), Some(MenuId::MAIN));
+    assert_eq!(tree.parent(MenuId::MAIN), None);
+    assert_eq!(tree.parent(MenuId(2)), None);
+}
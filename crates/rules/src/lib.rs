@@ -0,0 +1,5 @@
+mod rules;
+mod xp_table;
+
+pub use rules::RulesError;
+pub use xp_table::XpTable;
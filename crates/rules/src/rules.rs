@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RulesError {
+    #[error("entry {index}: '{value}' is not a number")]
+    InvalidNumber { index: u32, value: String },
+    #[error("entry {index}: expected {expected} values but found {found}")]
+    FieldCount {
+        index: u32,
+        expected: usize,
+        found: usize,
+    },
+    #[error("entry {index}: {reason}")]
+    Invalid { index: u32, reason: &'static str },
+}
+
+/// Splits the text of an entry into numbers, rules files separate them with commas and/or whitespace.
+pub(crate) fn numbers(index: u32, text: &str) -> Result<Vec<i32>, RulesError> {
+    text.split(|char: char| char == ',' || char.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value.parse().map_err(|_| RulesError::InvalidNumber {
+                index,
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn unsigned(index: u32, value: i32) -> Result<u32, RulesError> {
+    u32::try_from(value).map_err(|_| RulesError::Invalid {
+        index,
+        reason: "value must not be negative",
+    })
+}
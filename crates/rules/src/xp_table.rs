@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_mes::Mes;
+
+use crate::{
+    RulesError,
+    rules::{numbers, unsigned},
+};
+
+/// Experience awarded for defeating a critter, by the critter's level.
+/// Read from the `{level}{xp}` entries of `rules\xp_critter.mes`. The layout of the shipped file is not
+/// verified yet, so levels may start anywhere and have gaps, a level without an entry awards nothing.
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct XpTable {
+    xp: BTreeMap<u32, u32>,
+}
+
+impl XpTable {
+    pub fn from_mes(mes: &Mes) -> Result<XpTable, RulesError> {
+        let mut xp = BTreeMap::new();
+        for (index, text) in mes.iter() {
+            let values = numbers(index, text)?;
+            let [value] = values[..] else {
+                return Err(RulesError::FieldCount {
+                    index,
+                    expected: 1,
+                    found: values.len(),
+                });
+            };
+            xp.insert(index, unsigned(index, value)?);
+        }
+        Ok(XpTable { xp })
+    }
+
+    /// Experience for defeating a critter of `level`.
+    pub fn get(&self, level: u32) -> Option<u32> {
+        self.xp.get(&level).copied()
+    }
+
+    /// Highest level with an entry, 0 if the table is empty.
+    pub fn max_level(&self) -> u32 {
+        self.xp.keys().next_back().copied().unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_xp_table() {
+    let table =
+        XpTable::from_mes(&Mes::from_contents("{1}{0}\n{2}{ 100 }\n{5}{250}").unwrap()).unwrap();
+    assert_eq!(table.max_level(), 5);
+    assert_eq!(table.get(2), Some(100));
+    assert_eq!(table.get(0), None);
+    assert_eq!(table.get(3), None);
+    assert_eq!(table.get(6), None);
+
+    let error = |raw| XpTable::from_mes(&Mes::from_contents(raw).unwrap()).unwrap_err();
+    assert_eq!(
+        error("{1}{ten}"),
+        RulesError::InvalidNumber {
+            index: 1,
+            value: "ten".to_string()
+        }
+    );
+    assert!(matches!(
+        error("{1}{-10}"),
+        RulesError::Invalid { index: 1, .. }
+    ));
+}
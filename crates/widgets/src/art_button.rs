@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use bevy_art::Art;
+use bevy_image_font::{ImageTextInput, ImageTextInputSystems, text_input_focused};
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ArtButtonPressed>().add_systems(
+        Update,
+        (
+            press_buttons.before(ImageTextInputSystems),
+            show_button_frames,
+        )
+            .chain(),
+    );
+}
+
+/// Also sends `E` whenever a button with an [`OnArtButtonPress<E>`] is pressed.
+pub fn press_event<E: Event + Clone>(app: &mut App) {
+    app.add_event::<E>()
+        .add_systems(Update, send_press_events::<E>.after(press_buttons));
+}
+
+/// A button drawn with a frame of an interface art, picked by its [`Interaction`] out of the
+/// `#image` atlas and `#layout` the art is loaded with.
+/// Pressing it, by clicking or typing its hotkey, sends [`ArtButtonPressed`]. Hotkeys are
+/// ignored while an [`ImageTextInput`] has the focus.
+#[derive(Component, Clone, Debug)]
+#[require(Button, ImageNode, ShownFrame)]
+pub struct ArtButton {
+    pub art: Handle<Art>,
+    pub frames: ArtButtonFrames,
+    /// Disabled buttons show their disabled frame and can't be pressed.
+    pub disabled: bool,
+    pub hotkey: Option<KeyCode>,
+    pub hover_sound: Option<Handle<AudioSource>>,
+    pub press_sound: Option<Handle<AudioSource>>,
+}
+
+/// Frames of the art shown in each state. Arts with fewer frames show their last one instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArtButtonFrames {
+    pub normal: usize,
+    pub hovered: usize,
+    pub pressed: usize,
+    pub disabled: usize,
+}
+
+impl Default for ArtButtonFrames {
+    fn default() -> Self {
+        Self {
+            normal: 0,
+            hovered: 1,
+            pressed: 2,
+            disabled: 3,
+        }
+    }
+}
+
+/// Sent when an [`ArtButton`] is pressed.
+#[derive(Clone, Debug, Event)]
+pub struct ArtButtonPressed {
+    pub entity: Entity,
+}
+
+/// Event sent when its [`ArtButton`] is pressed, once registered with [`press_event`].
+#[derive(Component, Clone, Debug)]
+pub struct OnArtButtonPress<E: Event + Clone>(pub E);
+
+/// Frame the texture atlas of a button currently shows.
+#[derive(Component, Default)]
+struct ShownFrame(Option<usize>);
+
+impl ArtButton {
+    pub fn new(art: Handle<Art>) -> Self {
+        Self {
+            art,
+            frames: default(),
+            disabled: false,
+            hotkey: None,
+            hover_sound: None,
+            press_sound: None,
+        }
+    }
+
+    pub fn with_frames(mut self, frames: ArtButtonFrames) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    pub fn with_hotkey(mut self, hotkey: KeyCode) -> Self {
+        self.hotkey = Some(hotkey);
+        self
+    }
+
+    pub fn with_sounds(
+        mut self,
+        hover: Option<Handle<AudioSource>>,
+        press: Option<Handle<AudioSource>>,
+    ) -> Self {
+        self.hover_sound = hover;
+        self.press_sound = press;
+        self
+    }
+
+    /// Frame to show for `interaction` out of an art with `frame_count` frames.
+    pub fn frame(&self, interaction: Interaction, frame_count: usize) -> usize {
+        let frame = match (self.disabled, interaction) {
+            (true, _) => self.frames.disabled,
+            (false, Interaction::None) => self.frames.normal,
+            (false, Interaction::Hovered) => self.frames.hovered,
+            (false, Interaction::Pressed) => self.frames.pressed,
+        };
+        frame.min(frame_count.saturating_sub(1))
+    }
+}
+
+fn press_buttons(
+    keys: Res<ButtonInput<KeyCode>>,
+    inputs: Query<&ImageTextInput>,
+    buttons: Query<(Entity, &ArtButton, Ref<Interaction>)>,
+    mut pressed: EventWriter<ArtButtonPressed>,
+    mut commands: Commands,
+) {
+    let typing = text_input_focused(inputs);
+    for (entity, button, interaction) in &buttons {
+        if button.disabled {
+            continue;
+        }
+        let hotkey = !typing && button.hotkey.is_some_and(|key| keys.just_pressed(key));
+        let (sound, press) = match *interaction {
+            _ if hotkey => (&button.press_sound, true),
+            Interaction::Pressed if interaction.is_changed() => (&button.press_sound, true),
+            Interaction::Hovered if interaction.is_changed() => (&button.hover_sound, false),
+            _ => continue,
+        };
+        if let Some(sound) = sound {
+            commands.spawn((AudioPlayer(sound.clone()), PlaybackSettings::DESPAWN));
+        }
+        if press {
+            pressed.write(ArtButtonPressed { entity });
+        }
+    }
+}
+
+fn send_press_events<E: Event + Clone>(
+    mut pressed: EventReader<ArtButtonPressed>,
+    buttons: Query<&OnArtButtonPress<E>>,
+    mut events: EventWriter<E>,
+) {
+    for ArtButtonPressed { entity } in pressed.read() {
+        if let Ok(OnArtButtonPress(event)) = buttons.get(*entity) {
+            events.write(event.clone());
+        }
+    }
+}
+
+/// Shows the frame matching the state of each button once its art is loaded.
+fn show_button_frames(
+    arts: Res<Assets<Art>>,
+    asset_server: Res<AssetServer>,
+    mut buttons: Query<(&ArtButton, &Interaction, &mut ShownFrame, &mut ImageNode)>,
+) {
+    for (button, interaction, mut shown, mut image_node) in &mut buttons {
+        let (Some(art), Some(path)) = (arts.get(&button.art), button.art.path()) else {
+            continue;
+        };
+        let frame = button.frame(*interaction, art.frame_count());
+        if shown.0 == Some(frame) {
+            continue;
+        }
+        shown.0 = Some(frame);
+        match image_node.texture_atlas.as_mut() {
+            Some(atlas) => atlas.index = frame,
+            None => {
+                image_node.image = asset_server.load(path.clone().with_label("image"));
+                image_node.texture_atlas = Some(TextureAtlas {
+                    layout: asset_server.load(path.clone().with_label("layout")),
+                    index: frame,
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn test_frames() {
+    let mut button = ArtButton::new(Handle::default());
+    assert_eq!(button.frame(Interaction::None, 4), 0);
+    assert_eq!(button.frame(Interaction::Hovered, 4), 1);
+    assert_eq!(button.frame(Interaction::Pressed, 4), 2);
+    // arts without a disabled frame keep showing the pressed one
+    button.disabled = true;
+    assert_eq!(button.frame(Interaction::Hovered, 3), 2);
+    assert_eq!(button.frame(Interaction::Hovered, 0), 0);
+}
+
+#[test]
+fn test_press_buttons() {
+    #[derive(Clone, Event)]
+    struct Quit;
+
+    let mut app = App::new();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_event::<ArtButtonPressed>()
+        .add_systems(Update, press_buttons);
+    press_event::<Quit>(&mut app);
+    let button = app
+        .world_mut()
+        .spawn((
+            ArtButton::new(Handle::default()).with_hotkey(KeyCode::KeyQ),
+            OnArtButtonPress(Quit),
+        ))
+        .id();
+    let update = |app: &mut App, interaction: Option<Interaction>, hotkey: bool| {
+        if let Some(interaction) = interaction {
+            *app.world_mut().get_mut::<Interaction>(button).unwrap() = interaction;
+        }
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.clear();
+        if hotkey {
+            keys.press(KeyCode::KeyQ);
+        } else {
+            keys.release(KeyCode::KeyQ);
+        }
+        app.update();
+        let world = app.world_mut();
+        let pressed: Vec<_> = world
+            .resource_mut::<Events<ArtButtonPressed>>()
+            .drain()
+            .map(|pressed| pressed.entity)
+            .collect();
+        let quit = world.resource_mut::<Events<Quit>>().drain().count();
+        assert_eq!(pressed.len(), quit);
+        assert!(pressed.iter().all(|entity| *entity == button));
+        quit
+    };
+
+    assert_eq!(update(&mut app, None, false), 0);
+    assert_eq!(update(&mut app, Some(Interaction::Hovered), false), 0);
+    assert_eq!(update(&mut app, Some(Interaction::Pressed), false), 1);
+    // staying pressed doesn't press again
+    assert_eq!(update(&mut app, None, false), 0);
+    assert_eq!(update(&mut app, Some(Interaction::Hovered), true), 1);
+    assert_eq!(update(&mut app, None, true), 0);
+    // typing into a text input
+    let mut input = ImageTextInput::default();
+    input.focused = true;
+    let input = app.world_mut().spawn(input).id();
+    assert_eq!(update(&mut app, None, false), 0);
+    assert_eq!(update(&mut app, None, true), 0);
+    app.world_mut().despawn(input);
+
+    app.world_mut()
+        .get_mut::<ArtButton>(button)
+        .unwrap()
+        .disabled = true;
+    assert_eq!(update(&mut app, Some(Interaction::Pressed), false), 0);
+    assert_eq!(update(&mut app, Some(Interaction::Hovered), true), 0);
+}
@@ -0,0 +1,3 @@
+mod art_button;
+
+pub use art_button::*;
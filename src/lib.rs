@@ -3,19 +3,21 @@ use bevy::{
     color::palettes::css::BLACK,
     image::ImageSamplerDescriptor,
     prelude::*,
-    window::WindowMode,
 };
 use bevy_art::ArtPlugin;
 use bevy_dat::{Dat, DatPlugin};
 use bevy_image_font::ImageTextPlugin;
 use bevy_mes::MesPlugin;
 use common::state::AppState;
-use dat_repo::DatRepo;
+use config::DataDir;
+use dat_repo::{DAT_FILES, DatRepo, DatRequirement};
+use loading::{DAT_GROUP, LoadFinished, LoadManifest};
 
 pub struct RustcarnumPlugin;
 
 impl Plugin for RustcarnumPlugin {
     fn build(&self, app: &mut App) {
+        let data_dir = DataDir::resolve();
         let dat_repo = DatRepo::default();
         let reader = MemoryAssetReader {
             root: dat_repo.dir.clone(),
@@ -42,14 +44,26 @@ impl Plugin for RustcarnumPlugin {
                 })
                 .set(ImagePlugin {
                     default_sampler: ImageSamplerDescriptor::nearest(),
+                })
+                .set(AssetPlugin {
+                    file_path: data_dir.path.to_string_lossy().into_owned(),
+                    ..default()
                 }),
         )
+        .insert_resource(data_dir)
         .insert_resource(dat_repo)
         .insert_resource(ClearColor(BLACK.into()))
         .init_state::<AppState>()
         .enable_state_scoped_entities::<AppState>()
         .add_plugins((ArtPlugin, DatPlugin, ImageTextPlugin, MesPlugin))
-        .add_plugins((loading::plugin, main_menu::plugin, video::plugin))
+        .add_plugins((
+            config::plugin,
+            loading::plugin,
+            localization::plugin,
+            main_menu::plugin,
+            video::plugin,
+            widgets::plugin,
+        ))
         .add_systems(Startup, setup)
         .add_systems(
             Update,
@@ -58,27 +72,38 @@ impl Plugin for RustcarnumPlugin {
     }
 }
 
-#[derive(Component, Deref)]
-struct DatFiles(Vec<Handle<Dat>>);
-
-fn setup(mut dat_repo: ResMut<DatRepo>, asset_server: Res<AssetServer>, mut commands: Commands) {
+fn setup(
+    mut dat_repo: ResMut<DatRepo>,
+    mut manifest: ResMut<LoadManifest>,
+    data_dir: Res<DataDir>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
     commands.spawn(Camera2d);
 
-    dat_repo.add_dat(asset_server.load("tig.dat"));
-    dat_repo.add_dat(asset_server.load("arcanum1.dat"));
-    dat_repo.add_dat(asset_server.load("arcanum2.dat"));
-    dat_repo.add_dat(asset_server.load("arcanum3.dat"));
-    dat_repo.add_dat(asset_server.load("Arcanum4.dat"));
-    dat_repo.add_dat(asset_server.load("modules/Arcanum.dat"));
+    info!(
+        "loading game data from {}, set by {}",
+        data_dir.path.display(),
+        data_dir.source
+    );
+    for dat in DAT_FILES {
+        let handle = asset_server.load(dat.path);
+        match dat.requirement {
+            DatRequirement::Required => manifest.add(DAT_GROUP, handle.clone()),
+            DatRequirement::Optional => manifest.add_optional(DAT_GROUP, handle.clone()),
+        }
+        dat_repo.add_dat(handle);
+    }
 }
 
 fn wait_for_initializing(
+    mut events: EventReader<LoadFinished>,
+    manifest: Res<LoadManifest>,
     mut dat_repo: ResMut<DatRepo>,
     dats: Res<Assets<Dat>>,
-    asset_server: Res<AssetServer>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    if dat_repo.loaded_dat(&asset_server) {
+    if events.read().last().is_some() && manifest.is_done() {
         dat_repo.fill(&dats);
         next_state.set(AppState::first_video());
     }
@@ -73,10 +73,10 @@ fn main() -> std::io::Result<()> {
     let progress_bar = ProgressBar::new(entries.len() as u64);
     for entry in entries {
         let path = Path::new(&args.output_filepath).join(&entry.filepath);
-        if let Some(parent) = path.parent() {
-            if !fs::exists(parent)? {
-                fs::create_dir_all(parent)?;
-            }
+        if let Some(parent) = path.parent()
+            && !fs::exists(parent)?
+        {
+            fs::create_dir_all(parent)?;
         }
         match entry.entry_type {
             DatEntryType::Directory => {